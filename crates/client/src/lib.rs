@@ -0,0 +1,119 @@
+//! The client's side of a connection. Like the server, it tells the peer in an alert why it
+//! gave up, so both ends can log the cause of a failed handshake or channel.
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use protocol::framing::{read_frame, write_frame, FrameError};
+use protocol::handshake::{generate_client_hello, handle_server_hello, HandshakeConfig, Session};
+use protocol::messages::{Alert, Finished, ServerHello};
+use protocol::record::{handshake_message, Record};
+use protocol::session::{Incoming, SecureChannel};
+use protocol::Error;
+
+/// Tell the server in the clear why the handshake was abandoned; the connection is dropped
+/// either way, so a failed write is ignored
+async fn abort_handshake<W: AsyncWriteExt + Unpin>(writer: &mut W, error: Error) -> Error {
+    if let Some(description) = error.alert() {
+        let _ = write_frame(writer, &Record::alert(Alert::new(description)).to_bytes()).await;
+    }
+    error
+}
+
+/// The body of the server's next handshake message, or the error its alert reports
+async fn read_handshake_message<R: AsyncReadExt + Unpin>(reader: &mut R) -> Result<Vec<u8>, Error> {
+    let record = Record::from_bytes(&read_frame(reader).await?)?;
+    Ok(handshake_message(record)?)
+}
+
+async fn run_handshake<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    config: &HandshakeConfig,
+) -> Result<Session, Error>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    let (client_hello, state) = generate_client_hello(config);
+    write_frame(writer, &Record::handshake(client_hello.to_bytes()).to_bytes()).await?;
+
+    let server_hello = ServerHello::from_bytes(&read_handshake_message(reader).await?)?;
+    let mut session = handle_server_hello(server_hello, state)?;
+
+    let server_finished = Finished::from_bytes(&read_handshake_message(reader).await?)?;
+    session.verify_finished(&server_finished)?;
+    write_frame(writer, &Record::handshake(session.finished().to_bytes()).to_bytes()).await?;
+    Ok(session)
+}
+
+/// Run the client's side of the handshake up to a verified server Finished. A failure is
+/// reported to the server with `Error::alert` before it is returned.
+pub async fn handshake<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    config: &HandshakeConfig,
+) -> Result<Session, Error>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    match run_handshake(reader, writer, config).await {
+        Ok(session) => Ok(session),
+        Err(e) => Err(abort_handshake(writer, e).await),
+    }
+}
+
+/// The server's next application message, or `None` once it has sent close_notify. Key
+/// updates and re-key steps the server sends first are handled on the way. A record that
+/// fails is answered with its alert; only a replayed record is dropped without ending the
+/// channel. A stream that ends before the server's close_notify is
+/// `ChannelError::Truncated`.
+pub async fn receive<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    channel: &mut SecureChannel,
+) -> Result<Option<Vec<u8>>, Error>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    loop {
+        let frame = match read_frame(reader).await {
+            Ok(frame) => frame,
+            // Only a stream that ended after the server's close_notify is a clean disconnect
+            Err(FrameError::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                channel.end_of_stream()?;
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let record = match Record::from_bytes(&frame) {
+            Ok(record) => record,
+            Err(e) => {
+                write_frame(writer, &channel.alert(e.alert()).to_bytes()).await?;
+                return Err(e.into());
+            }
+        };
+
+        match channel.decrypt(&record) {
+            Ok(Incoming::Data(plaintext)) => return Ok(Some(plaintext)),
+            Ok(Incoming::KeyUpdate) => {}
+            Ok(Incoming::Rekey { reply }) => {
+                for record in reply {
+                    write_frame(writer, &record.to_bytes()).await?;
+                }
+            }
+            Ok(Incoming::CloseNotify) => return Ok(None),
+            Err(e) => {
+                if let Some(description) = e.alert() {
+                    write_frame(writer, &channel.alert(description).to_bytes()).await?;
+                }
+                let e = Error::from(e);
+                if e.is_fatal() {
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
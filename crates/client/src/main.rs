@@ -1,9 +1,9 @@
 use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::TcpStream;
 
-use protocol::framing::{read_frame, write_frame};
-use protocol::handshake::{generate_client_hello, handle_server_hello};
-use protocol::messages::{AppData, ServerHello};
+use hybrid_kyber_client::{handshake, receive};
+use protocol::framing::write_frame;
+use protocol::handshake::HandshakeConfig;
 use protocol::session::SecureChannel;
 
 #[tokio::main]
@@ -14,19 +14,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let (mut reader, mut writer) = socket.into_split();
 
     // --- Handshake ---
-    let (client_hello, state) = generate_client_hello();
-    write_frame(&mut writer, &client_hello.to_bytes()).await?;
-
-    let server_hello_bytes = read_frame(&mut reader).await?;
-    let server_hello =
-        ServerHello::from_bytes(&server_hello_bytes).map_err(|_| "Invalid ServerHello")?;
-
-    let session = handle_server_hello(server_hello, state)
-        .map_err(|e| format!("Handshake failed: {:?}", e))?;
+    let session = handshake(&mut reader, &mut writer, &HandshakeConfig::default()).await?;
 
     println!("Handshake complete! Quantum-resistant channel established.\n");
 
-    let mut channel = SecureChannel::new(session.keys, session.transcript, true);
+    let mut channel = SecureChannel::new(session)?;
 
     // --- Message Loop (request-response) ---
     let stdin = BufReader::new(io::stdin());
@@ -40,7 +32,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         let line = match lines.next_line().await? {
             Some(l) => l,
-            None => break,
+            None => {
+                write_frame(&mut writer, &channel.close().to_bytes()).await?;
+                break;
+            }
         };
 
         if line.is_empty() {
@@ -48,17 +43,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         // Encrypt and send
-        let encrypted = channel.encrypt(line.as_bytes());
-        write_frame(&mut writer, &encrypted.to_bytes()).await?;
-
-        // Read response
-        let response_frame = read_frame(&mut reader).await?;
-        let response_data =
-            AppData::from_bytes(&response_frame).map_err(|_| "Invalid AppData")?;
+        for record in channel.encrypt(line.as_bytes()) {
+            write_frame(&mut writer, &record.to_bytes()).await?;
+        }
 
-        let plaintext = channel
-            .decrypt(&response_data)
-            .map_err(|e| format!("Decryption failed: {:?}", e))?;
+        // Read response; a record that fails is reported to the server before giving up
+        let Some(plaintext) = receive(&mut reader, &mut writer, &mut channel).await? else {
+            println!("Server closed the connection");
+            return Ok(());
+        };
 
         let message = String::from_utf8_lossy(&plaintext);
         println!("[server] {}", message);
@@ -0,0 +1,132 @@
+use hybrid_kyber_client::{handshake, receive};
+use protocol::framing::{read_frame, write_frame};
+use protocol::handshake::{handle_client_hello, HandshakeConfig, HandshakeError, Session};
+use protocol::messages::{AlertDescription, ClientHello, Finished};
+use protocol::record::{handshake_message, Record};
+use protocol::session::{ChannelError, SecureChannel};
+use protocol::Error;
+use tokio::io::{duplex, split, AsyncReadExt, AsyncWriteExt};
+
+/// The body of the client's next handshake message, or the error the server would log
+async fn read_handshake_message<R: AsyncReadExt + Unpin>(reader: &mut R) -> Result<Vec<u8>, Error> {
+    let record = Record::from_bytes(&read_frame(reader).await?)?;
+    Ok(handshake_message(record)?)
+}
+
+/// The server's side of the handshake, as the server binary runs it, with its Finished
+/// optionally corrupted on the way out
+async fn serve_handshake<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    corrupt: bool,
+) -> Result<Session, Error>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    let client_hello = ClientHello::from_bytes(&read_handshake_message(reader).await?)?;
+    let (server_hello, mut session) =
+        handle_client_hello(client_hello, &HandshakeConfig::default())?;
+
+    let mut finished = session.finished();
+    if corrupt {
+        finished.verify_data[0] ^= 1;
+    }
+    write_frame(writer, &Record::handshake(server_hello.to_bytes()).to_bytes()).await?;
+    write_frame(writer, &Record::handshake(finished.to_bytes()).to_bytes()).await?;
+
+    let client_finished = Finished::from_bytes(&read_handshake_message(reader).await?)?;
+    session.verify_finished(&client_finished)?;
+    Ok(session)
+}
+
+#[tokio::test]
+async fn test_server_logs_client_handshake_alert() {
+    let (client_io, server_io) = duplex(1 << 16);
+    let (mut client_reader, mut client_writer) = split(client_io);
+    let (mut server_reader, mut server_writer) = split(server_io);
+
+    let server = tokio::spawn(async move {
+        serve_handshake(&mut server_reader, &mut server_writer, true).await
+    });
+
+    let config = HandshakeConfig::default();
+    let error = handshake(&mut client_reader, &mut client_writer, &config).await.unwrap_err();
+    assert!(matches!(error, Error::Handshake(HandshakeError::InvalidFinished)));
+
+    let logged = server.await.unwrap().unwrap_err();
+    assert!(matches!(
+        logged,
+        Error::Handshake(HandshakeError::AlertReceived(AlertDescription::DecryptError))
+    ));
+}
+
+#[tokio::test]
+async fn test_server_logs_client_channel_alert() {
+    let (client_io, server_io) = duplex(1 << 16);
+    let (mut client_reader, mut client_writer) = split(client_io);
+    let (mut server_reader, mut server_writer) = split(server_io);
+
+    let server = tokio::spawn(async move {
+        let session = serve_handshake(&mut server_reader, &mut server_writer, false).await.unwrap();
+        let mut channel = SecureChannel::new(session).unwrap();
+
+        let mut record = channel.encrypt(b"hello").remove(0);
+        let last = record.payload.len() - 1;
+        record.payload[last] ^= 1;
+        write_frame(&mut server_writer, &record.to_bytes()).await.unwrap();
+
+        let alert = Record::from_bytes(&read_frame(&mut server_reader).await.unwrap()).unwrap();
+        channel.decrypt(&alert).unwrap_err()
+    });
+
+    let config = HandshakeConfig::default();
+    let session = handshake(&mut client_reader, &mut client_writer, &config).await.unwrap();
+    let mut channel = SecureChannel::new(session).unwrap();
+    let error = receive(&mut client_reader, &mut client_writer, &mut channel).await.unwrap_err();
+    assert!(matches!(error, Error::Channel(ChannelError::DecryptionFailed(_))));
+
+    let logged = server.await.unwrap();
+    assert!(matches!(logged, ChannelError::AlertReceived(AlertDescription::BadRecordMac)));
+}
+
+#[tokio::test]
+async fn test_client_detects_truncation() {
+    let (client_io, server_io) = duplex(1 << 16);
+    let (mut client_reader, mut client_writer) = split(client_io);
+    let (mut server_reader, mut server_writer) = split(server_io);
+
+    // The server finishes the handshake, then drops the stream without a close_notify
+    let server = tokio::spawn(async move {
+        serve_handshake(&mut server_reader, &mut server_writer, false).await.unwrap();
+    });
+
+    let config = HandshakeConfig::default();
+    let session = handshake(&mut client_reader, &mut client_writer, &config).await.unwrap();
+    let mut channel = SecureChannel::new(session).unwrap();
+    server.await.unwrap();
+    let error = receive(&mut client_reader, &mut client_writer, &mut channel).await.unwrap_err();
+    assert!(matches!(error, Error::Channel(ChannelError::Truncated)));
+}
+
+#[tokio::test]
+async fn test_client_accepts_end_of_stream_after_close_notify() {
+    let (client_io, server_io) = duplex(1 << 16);
+    let (mut client_reader, mut client_writer) = split(client_io);
+    let (mut server_reader, mut server_writer) = split(server_io);
+
+    let server = tokio::spawn(async move {
+        let session = serve_handshake(&mut server_reader, &mut server_writer, false).await.unwrap();
+        let mut channel = SecureChannel::new(session).unwrap();
+        write_frame(&mut server_writer, &channel.close().to_bytes()).await.unwrap();
+    });
+
+    let config = HandshakeConfig::default();
+    let session = handshake(&mut client_reader, &mut client_writer, &config).await.unwrap();
+    let mut channel = SecureChannel::new(session).unwrap();
+    server.await.unwrap();
+    let received = receive(&mut client_reader, &mut client_writer, &mut channel).await;
+    assert!(matches!(received, Ok(None)));
+    let received = receive(&mut client_reader, &mut client_writer, &mut channel).await;
+    assert!(matches!(received, Ok(None)));
+}
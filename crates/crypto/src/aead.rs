@@ -1,11 +1,24 @@
-use chacha20poly1305::aead::AeadInPlace;
-use chacha20poly1305::{ChaCha20Poly1305, KeyInit, Nonce, Tag};
+use aes_gcm::Aes256Gcm;
+use chacha20poly1305::aead::consts::{U12, U16};
+use chacha20poly1305::aead::{AeadInPlace, Nonce, Tag};
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit};
+use thiserror::Error;
+
+use crate::traits::Aead;
 
 pub const TAG_SIZE: usize = 16;
 
-#[derive(Debug)]
+/// The ciphertext failed authentication or was shorter than a tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("AEAD authentication failed")]
 pub struct AeadError;
 
+/// ChaCha20-Poly1305 (RFC 8439)
+pub struct ChaCha20Poly1305Aead;
+
+/// AES-256-GCM (NIST SP 800-38D)
+pub struct Aes256GcmAead;
+
 fn compute_nonce(nonce_base: &[u8; 12], seq: u64) -> [u8; 12] {
     let mut nonce = *nonce_base;
     let seq_bytes = seq.to_be_bytes();
@@ -15,16 +28,13 @@ fn compute_nonce(nonce_base: &[u8; 12], seq: u64) -> [u8; 12] {
     nonce
 }
 
-pub fn encrypt(
-    key: &[u8; 32],
-    nonce_base: &[u8; 12],
-    seq: u64,
-    aad: &[u8],
-    plaintext: &[u8],
-) -> Vec<u8> {
+fn seal<C>(key: &[u8; 32], nonce_base: &[u8; 12], seq: u64, aad: &[u8], plaintext: &[u8]) -> Vec<u8>
+where
+    C: KeyInit + AeadInPlace<NonceSize = U12, TagSize = U16>,
+{
     let nonce_bytes = compute_nonce(nonce_base, seq);
-    let nonce = Nonce::from_slice(&nonce_bytes);
-    let cipher = ChaCha20Poly1305::new_from_slice(key).expect("valid key length");
+    let nonce = Nonce::<C>::from_slice(&nonce_bytes);
+    let cipher = C::new_from_slice(key).expect("valid key length");
 
     let mut buffer = plaintext.to_vec();
     let tag = cipher
@@ -35,24 +45,27 @@ pub fn encrypt(
     buffer
 }
 
-pub fn decrypt(
+fn open<C>(
     key: &[u8; 32],
     nonce_base: &[u8; 12],
     seq: u64,
     aad: &[u8],
     ciphertext_with_tag: &[u8],
-) -> Result<Vec<u8>, AeadError> {
+) -> Result<Vec<u8>, AeadError>
+where
+    C: KeyInit + AeadInPlace<NonceSize = U12, TagSize = U16>,
+{
     if ciphertext_with_tag.len() < TAG_SIZE {
         return Err(AeadError);
     }
 
     let nonce_bytes = compute_nonce(nonce_base, seq);
-    let nonce = Nonce::from_slice(&nonce_bytes);
-    let cipher = ChaCha20Poly1305::new_from_slice(key).expect("valid key length");
+    let nonce = Nonce::<C>::from_slice(&nonce_bytes);
+    let cipher = C::new_from_slice(key).expect("valid key length");
 
     let ct_len = ciphertext_with_tag.len() - TAG_SIZE;
     let mut buffer = ciphertext_with_tag[..ct_len].to_vec();
-    let tag = Tag::from_slice(&ciphertext_with_tag[ct_len..]);
+    let tag = Tag::<C>::from_slice(&ciphertext_with_tag[ct_len..]);
 
     cipher
         .decrypt_in_place_detached(nonce, aad, &mut buffer, tag)
@@ -60,3 +73,69 @@ pub fn decrypt(
 
     Ok(buffer)
 }
+
+impl Aead for ChaCha20Poly1305Aead {
+    fn encrypt(
+        key: &[u8; 32],
+        nonce_base: &[u8; 12],
+        seq: u64,
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> Vec<u8> {
+        seal::<ChaCha20Poly1305>(key, nonce_base, seq, aad, plaintext)
+    }
+
+    fn decrypt(
+        key: &[u8; 32],
+        nonce_base: &[u8; 12],
+        seq: u64,
+        aad: &[u8],
+        ciphertext_with_tag: &[u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        open::<ChaCha20Poly1305>(key, nonce_base, seq, aad, ciphertext_with_tag)
+    }
+}
+
+impl Aead for Aes256GcmAead {
+    fn encrypt(
+        key: &[u8; 32],
+        nonce_base: &[u8; 12],
+        seq: u64,
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> Vec<u8> {
+        seal::<Aes256Gcm>(key, nonce_base, seq, aad, plaintext)
+    }
+
+    fn decrypt(
+        key: &[u8; 32],
+        nonce_base: &[u8; 12],
+        seq: u64,
+        aad: &[u8],
+        ciphertext_with_tag: &[u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        open::<Aes256Gcm>(key, nonce_base, seq, aad, ciphertext_with_tag)
+    }
+}
+
+/// ChaCha20-Poly1305 encryption, kept for callers predating the `Aead` trait
+pub fn encrypt(
+    key: &[u8; 32],
+    nonce_base: &[u8; 12],
+    seq: u64,
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Vec<u8> {
+    ChaCha20Poly1305Aead::encrypt(key, nonce_base, seq, aad, plaintext)
+}
+
+/// ChaCha20-Poly1305 decryption, kept for callers predating the `Aead` trait
+pub fn decrypt(
+    key: &[u8; 32],
+    nonce_base: &[u8; 12],
+    seq: u64,
+    aad: &[u8],
+    ciphertext_with_tag: &[u8],
+) -> Result<Vec<u8>, AeadError> {
+    ChaCha20Poly1305Aead::decrypt(key, nonce_base, seq, aad, ciphertext_with_tag)
+}
@@ -0,0 +1,80 @@
+use std::marker::PhantomData;
+
+use rand::{CryptoRng, RngCore};
+use subtle::ConstantTimeEq;
+
+use crate::secret::SecretBytes;
+use crate::traits::{Dh, Kem, KemError};
+
+/// Any `Dh` group used as a KEM: the ciphertext is an ephemeral public key and the
+/// shared secret is the raw DH output, left for the hybrid combiner to hash
+pub struct DhKem<D> {
+    _marker: PhantomData<D>,
+}
+
+/// Reject an all-zero DH output, which a small-order peer point forces regardless of our key
+fn contributory(ss: SecretBytes) -> Result<SecretBytes, KemError> {
+    if bool::from(ss.ct_eq(&vec![0u8; ss.len()])) {
+        return Err(KemError::NonContributory);
+    }
+    Ok(ss)
+}
+
+impl<D> Kem for DhKem<D>
+where
+    D: Dh,
+    D::SharedSecret: AsRef<[u8]>,
+{
+    type PublicKey = D::PublicKey;
+    type SecretKey = D::SecretKey;
+    type Ciphertext = D::PublicKey;
+    type SharedSecret = SecretBytes;
+
+    const PUBLIC_KEY_SIZE: usize = D::PUBLIC_KEY_SIZE;
+    const SECRET_KEY_SIZE: usize = D::SECRET_KEY_SIZE;
+    const CIPHERTEXT_SIZE: usize = D::PUBLIC_KEY_SIZE;
+    const SHARED_SECRET_SIZE: usize = D::SHARED_SECRET_SIZE;
+
+    fn generate_keypair_with_rng<R: CryptoRng + RngCore>(
+        rng: &mut R,
+    ) -> (Self::PublicKey, Self::SecretKey) {
+        D::generate_keypair_with_rng(rng)
+    }
+
+    fn encapsulate_with_rng<R: CryptoRng + RngCore>(
+        pk: &Self::PublicKey,
+        rng: &mut R,
+    ) -> Result<(Self::Ciphertext, Self::SharedSecret), KemError> {
+        let (eph_pk, eph_sk) = D::generate_keypair_with_rng(rng);
+        let ss = contributory(SecretBytes::from(D::diffie_hellman(&eph_sk, pk).as_ref()))?;
+        Ok((eph_pk, ss))
+    }
+
+    fn decapsulate(sk: &Self::SecretKey, ct: &Self::Ciphertext) -> Result<Self::SharedSecret, KemError> {
+        contributory(SecretBytes::from(D::diffie_hellman(sk, ct).as_ref()))
+    }
+
+    fn public_key_to_bytes(pk: &Self::PublicKey) -> Vec<u8> {
+        D::public_key_to_bytes(pk)
+    }
+
+    fn public_key_from_bytes(bytes: &[u8]) -> Result<Self::PublicKey, KemError> {
+        D::public_key_from_bytes(bytes).ok_or(KemError::InvalidPublicKey)
+    }
+
+    fn secret_key_to_bytes(sk: &Self::SecretKey) -> SecretBytes {
+        D::secret_key_to_bytes(sk)
+    }
+
+    fn secret_key_from_bytes(bytes: &[u8]) -> Result<Self::SecretKey, KemError> {
+        D::secret_key_from_bytes(bytes).ok_or(KemError::InvalidSecretKey)
+    }
+
+    fn ciphertext_to_bytes(ct: &Self::Ciphertext) -> Vec<u8> {
+        D::public_key_to_bytes(ct)
+    }
+
+    fn ciphertext_from_bytes(bytes: &[u8]) -> Result<Self::Ciphertext, KemError> {
+        D::public_key_from_bytes(bytes).ok_or(KemError::InvalidCiphertext)
+    }
+}
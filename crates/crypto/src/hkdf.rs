@@ -1,8 +1,165 @@
+use std::fmt;
+
 use hkdf::Hkdf;
-use sha2::Sha256;
+use hmac::{Hmac, Mac};
+use sha2::Digest;
+pub use sha2::{Sha256, Sha384};
+use thiserror::Error;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::secret::SecretBytes;
 
 const INFO: &[u8] = b"hybrid-pq-channel-v1";
+/// Prefix for every `HKDF-Expand-Label` label, the analogue of TLS 1.3's "tls13 "
+const LABEL_PREFIX: &[u8] = b"hpqc ";
+/// Longest exporter label that still fits the one-byte `HKDF-Expand-Label` label length
+pub const MAX_EXPORTER_LABEL_LEN: usize = 255 - LABEL_PREFIX.len();
+
+/// Why the exporter refused a request; the context is hashed first, so any length is accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ExportError {
+    #[error("exporter label longer than {MAX_EXPORTER_LABEL_LEN} bytes")]
+    LabelTooLong,
+    /// `HKDF-Expand` produces at most 255 hash lengths
+    #[error("exporter output longer than 255 hash lengths")]
+    LengthTooLong,
+}
+
+/// Hash underlying HKDF, the Finished MAC and the handshake transcript
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha384,
+}
+
+impl HashAlgorithm {
+    /// Digest length, and so the length of every secret in the key schedule
+    pub const fn output_len(self) -> usize {
+        match self {
+            HashAlgorithm::Sha256 => 32,
+            HashAlgorithm::Sha384 => 48,
+        }
+    }
+
+    pub fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+            HashAlgorithm::Sha384 => Sha384::digest(data).to_vec(),
+        }
+    }
+
+    /// An empty incremental hash, for inputs that arrive piece by piece
+    pub fn running(self) -> RunningHash {
+        match self {
+            HashAlgorithm::Sha256 => RunningHash::Sha256(Sha256::new()),
+            HashAlgorithm::Sha384 => RunningHash::Sha384(Sha384::new()),
+        }
+    }
+
+    /// `HKDF-Extract`; the PRK is one hash long
+    pub fn extract(self, salt: &[u8], ikm: &[u8]) -> SecretBytes {
+        match self {
+            HashAlgorithm::Sha256 => Hkdf::<Sha256>::extract(Some(salt), ikm).0.as_slice().into(),
+            HashAlgorithm::Sha384 => Hkdf::<Sha384>::extract(Some(salt), ikm).0.as_slice().into(),
+        }
+    }
 
+    /// `HKDF-Expand`. Panics if `prk` is shorter than one hash or `out` exceeds 255 hash lengths.
+    pub fn expand(self, prk: &[u8], info: &[u8], out: &mut [u8]) {
+        match self {
+            HashAlgorithm::Sha256 => Hkdf::<Sha256>::from_prk(prk)
+                .expect("PRK is one hash long")
+                .expand(info, out),
+            HashAlgorithm::Sha384 => Hkdf::<Sha384>::from_prk(prk)
+                .expect("PRK is one hash long")
+                .expand(info, out),
+        }
+        .expect("valid length");
+    }
+
+    pub fn hmac(self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha256 => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("any key length");
+                mac.update(data);
+                mac.finalize().into_bytes().to_vec()
+            }
+            HashAlgorithm::Sha384 => {
+                let mut mac = Hmac::<Sha384>::new_from_slice(key).expect("any key length");
+                mac.update(data);
+                mac.finalize().into_bytes().to_vec()
+            }
+        }
+    }
+
+    /// Check an HMAC tag in constant time
+    pub fn verify_hmac(self, key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+        match self {
+            HashAlgorithm::Sha256 => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("any key length");
+                mac.update(data);
+                mac.verify_slice(tag).is_ok()
+            }
+            HashAlgorithm::Sha384 => {
+                let mut mac = Hmac::<Sha384>::new_from_slice(key).expect("any key length");
+                mac.update(data);
+                mac.verify_slice(tag).is_ok()
+            }
+        }
+    }
+}
+
+/// A hash named as a type, for components that fix their KDF at compile time
+pub trait KdfHashFunction {
+    const ALGORITHM: HashAlgorithm;
+}
+
+impl KdfHashFunction for Sha256 {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Sha256;
+}
+
+impl KdfHashFunction for Sha384 {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Sha384;
+}
+
+/// Incremental hash under a `HashAlgorithm`. `snapshot` reads the digest of everything fed
+/// so far without ending the hash, so one running state serves several derivation stages.
+#[derive(Clone)]
+pub enum RunningHash {
+    Sha256(Sha256),
+    Sha384(Sha384),
+}
+
+impl RunningHash {
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            RunningHash::Sha256(hasher) => hasher.update(data),
+            RunningHash::Sha384(hasher) => hasher.update(data),
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<u8> {
+        match self {
+            RunningHash::Sha256(hasher) => hasher.clone().finalize().to_vec(),
+            RunningHash::Sha384(hasher) => hasher.clone().finalize().to_vec(),
+        }
+    }
+
+    pub fn algorithm(&self) -> HashAlgorithm {
+        match self {
+            RunningHash::Sha256(_) => HashAlgorithm::Sha256,
+            RunningHash::Sha384(_) => HashAlgorithm::Sha384,
+        }
+    }
+}
+
+impl fmt::Debug for RunningHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RunningHash").field(&self.algorithm()).finish()
+    }
+}
+
+/// AEAD keys and nonce bases for both directions; wiped on drop
 pub struct SessionKeys {
     pub k_client_to_server: [u8; 32],
     pub k_server_to_client: [u8; 32],
@@ -10,17 +167,67 @@ pub struct SessionKeys {
     pub nonce_base_s2c: [u8; 12],
 }
 
-/// Derive session keys from shared secrets and transcript
+impl Zeroize for SessionKeys {
+    fn zeroize(&mut self) {
+        self.k_client_to_server.zeroize();
+        self.k_server_to_client.zeroize();
+        self.nonce_base_c2s.zeroize();
+        self.nonce_base_s2c.zeroize();
+    }
+}
+
+impl Drop for SessionKeys {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for SessionKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionKeys").finish_non_exhaustive()
+    }
+}
+
+/// MAC keys for the client and server Finished messages, one hash long each
+#[derive(Debug)]
+pub struct FinishedKeys {
+    pub client: SecretBytes,
+    pub server: SecretBytes,
+}
+
+/// Derive session keys from shared secrets and transcript.
+/// Single-shot v1 derivation, kept for compatibility; new code uses `KeySchedule`.
 pub fn derive_session_keys(
     ss_pq: &[u8],
     ss_classical: &[u8],
     transcript: &[u8; 32],
 ) -> SessionKeys {
-    let ikm = [ss_pq, ss_classical].concat();
-    let hk = Hkdf::<Sha256>::new(Some(transcript), &ikm);
+    derive_session_keys_with_hash(HashAlgorithm::Sha256, ss_pq, ss_classical, transcript)
+}
+
+/// The v1 derivation under any suite hash, with a transcript one `hash` output long
+pub fn derive_session_keys_with_hash(
+    hash: HashAlgorithm,
+    ss_pq: &[u8],
+    ss_classical: &[u8],
+    transcript: &[u8],
+) -> SessionKeys {
+    derive_session_keys_with_info(hash, ss_pq, ss_classical, transcript, INFO)
+}
+
+/// Derive session keys under a caller-chosen HKDF info label
+pub fn derive_session_keys_with_info(
+    hash: HashAlgorithm,
+    ss_pq: &[u8],
+    ss_classical: &[u8],
+    transcript: &[u8],
+    info: &[u8],
+) -> SessionKeys {
+    let ikm = SecretBytes::from([ss_pq, ss_classical].concat());
+    let prk = hash.extract(transcript, &ikm);
 
     let mut okm = [0u8; 88];
-    hk.expand(INFO, &mut okm).expect("valid length");
+    hash.expand(&prk, info, &mut okm);
 
     let mut k_client_to_server = [0u8; 32];
     let mut k_server_to_client = [0u8; 32];
@@ -31,6 +238,7 @@ pub fn derive_session_keys(
     k_server_to_client.copy_from_slice(&okm[32..64]);
     nonce_base_c2s.copy_from_slice(&okm[64..76]);
     nonce_base_s2c.copy_from_slice(&okm[76..88]);
+    okm.zeroize();
 
     SessionKeys {
         k_client_to_server,
@@ -39,3 +247,223 @@ pub fn derive_session_keys(
         nonce_base_s2c,
     }
 }
+
+/// TLS 1.3-style key schedule: early, handshake and master secrets with per-direction traffic secrets
+pub struct KeySchedule {
+    hash: HashAlgorithm,
+    transcript: Vec<u8>,
+    client_handshake_traffic: SecretBytes,
+    server_handshake_traffic: SecretBytes,
+    client_application_traffic: SecretBytes,
+    server_application_traffic: SecretBytes,
+    exporter_master: SecretBytes,
+    resumption_master: SecretBytes,
+}
+
+impl fmt::Debug for KeySchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeySchedule")
+            .field("hash", &self.hash)
+            .finish_non_exhaustive()
+    }
+}
+
+/// `HKDF-Expand-Label` (RFC 8446 §7.1) with this protocol's label prefix
+pub fn hkdf_expand_label(
+    hash: HashAlgorithm,
+    secret: &[u8],
+    label: &[u8],
+    context: &[u8],
+    out: &mut [u8],
+) {
+    let full_label_len = LABEL_PREFIX.len() + label.len();
+    assert!(full_label_len <= 255 && context.len() <= 255 && out.len() <= u16::MAX as usize);
+
+    let mut hkdf_label = Vec::with_capacity(4 + full_label_len + context.len());
+    hkdf_label.extend_from_slice(&(out.len() as u16).to_be_bytes());
+    hkdf_label.push(full_label_len as u8);
+    hkdf_label.extend_from_slice(LABEL_PREFIX);
+    hkdf_label.extend_from_slice(label);
+    hkdf_label.push(context.len() as u8);
+    hkdf_label.extend_from_slice(context);
+
+    hash.expand(secret, &hkdf_label, out);
+}
+
+/// `Derive-Secret`: expand a secret under a label and a transcript hash
+pub fn derive_secret(
+    hash: HashAlgorithm,
+    secret: &[u8],
+    label: &[u8],
+    transcript: &[u8],
+) -> SecretBytes {
+    let mut out = vec![0u8; hash.output_len()];
+    hkdf_expand_label(hash, secret, label, transcript, &mut out);
+    SecretBytes::from(out)
+}
+
+/// AEAD key and nonce base for one direction of a traffic secret, wiped when dropped
+pub fn traffic_key_iv(
+    hash: HashAlgorithm,
+    traffic_secret: &[u8],
+) -> (Zeroizing<[u8; 32]>, Zeroizing<[u8; 12]>) {
+    let mut key = Zeroizing::new([0u8; 32]);
+    let mut iv = Zeroizing::new([0u8; 12]);
+    hkdf_expand_label(hash, traffic_secret, b"key", b"", &mut *key);
+    hkdf_expand_label(hash, traffic_secret, b"iv", b"", &mut *iv);
+    (key, iv)
+}
+
+/// The traffic secret that replaces `traffic_secret` after a key update
+pub fn next_traffic_secret(hash: HashAlgorithm, traffic_secret: &[u8]) -> SecretBytes {
+    derive_secret(hash, traffic_secret, b"traffic upd", b"")
+}
+
+/// RFC 8446 §7.5 exporter: keying material bound to `label` and `context`. Lengths are
+/// checked before anything is derived, so an oversized request fails instead of panicking.
+pub fn export_keying_material(
+    hash: HashAlgorithm,
+    exporter_master: &[u8],
+    label: &[u8],
+    context: &[u8],
+    len: usize,
+) -> Result<Zeroizing<Vec<u8>>, ExportError> {
+    if label.len() > MAX_EXPORTER_LABEL_LEN {
+        return Err(ExportError::LabelTooLong);
+    }
+    if len > 255 * hash.output_len() {
+        return Err(ExportError::LengthTooLong);
+    }
+    let mut out = Zeroizing::new(vec![0u8; len]);
+    let secret = derive_secret(hash, exporter_master, label, &hash.digest(b""));
+    hkdf_expand_label(hash, &secret, b"exporter", &hash.digest(context), &mut out);
+    Ok(out)
+}
+
+/// Mix a fresh hybrid shared secret into a traffic secret, for post-compromise re-keying
+pub fn rekey_traffic_secret(
+    hash: HashAlgorithm,
+    traffic_secret: &[u8],
+    shared_secret: &[u8],
+    rekey_transcript: &[u8],
+) -> SecretBytes {
+    let prk = hash.extract(traffic_secret, shared_secret);
+    derive_secret(hash, &prk, b"rekey traffic", rekey_transcript)
+}
+
+impl KeySchedule {
+    /// Run the schedule over the combined hybrid shared secret and the handshake transcript
+    pub fn new(hash: HashAlgorithm, shared_secret: &[u8], transcript: &[u8]) -> Self {
+        let zeros = vec![0u8; hash.output_len()];
+        // Hash of the empty string, the transcript context for "derived"
+        let empty = hash.digest(b"");
+
+        // No pre-shared key yet, so the early secret is extracted from zeros
+        let early_secret = hash.extract(&zeros, &zeros);
+
+        let handshake_secret = hash.extract(
+            &derive_secret(hash, &early_secret, b"derived", &empty),
+            shared_secret,
+        );
+        let client_handshake_traffic =
+            derive_secret(hash, &handshake_secret, b"c hs traffic", transcript);
+        let server_handshake_traffic =
+            derive_secret(hash, &handshake_secret, b"s hs traffic", transcript);
+
+        let master_secret = hash.extract(
+            &derive_secret(hash, &handshake_secret, b"derived", &empty),
+            &zeros,
+        );
+        let client_application_traffic =
+            derive_secret(hash, &master_secret, b"c ap traffic", transcript);
+        let server_application_traffic =
+            derive_secret(hash, &master_secret, b"s ap traffic", transcript);
+        let exporter_master = derive_secret(hash, &master_secret, b"exp master", transcript);
+        let resumption_master = derive_secret(hash, &master_secret, b"res master", transcript);
+
+        Self {
+            hash,
+            transcript: transcript.to_vec(),
+            client_handshake_traffic,
+            server_handshake_traffic,
+            client_application_traffic,
+            server_application_traffic,
+            exporter_master,
+            resumption_master,
+        }
+    }
+
+    pub fn hash(&self) -> HashAlgorithm {
+        self.hash
+    }
+
+    pub fn transcript(&self) -> &[u8] {
+        &self.transcript
+    }
+
+    pub fn client_application_traffic_secret(&self) -> &[u8] {
+        &self.client_application_traffic
+    }
+
+    pub fn server_application_traffic_secret(&self) -> &[u8] {
+        &self.server_application_traffic
+    }
+
+    pub fn exporter_master_secret(&self) -> &[u8] {
+        &self.exporter_master
+    }
+
+    pub fn resumption_master_secret(&self) -> &[u8] {
+        &self.resumption_master
+    }
+
+    pub fn export_keying_material(
+        &self,
+        label: &[u8],
+        context: &[u8],
+        len: usize,
+    ) -> Result<Zeroizing<Vec<u8>>, ExportError> {
+        export_keying_material(self.hash, &self.exporter_master, label, context, len)
+    }
+
+    /// Finished MAC keys, expanded from the handshake traffic secrets
+    pub fn finished_keys(&self) -> FinishedKeys {
+        let mut client = vec![0u8; self.hash.output_len()];
+        let mut server = vec![0u8; self.hash.output_len()];
+        hkdf_expand_label(self.hash, &self.client_handshake_traffic, b"finished", b"", &mut client);
+        hkdf_expand_label(self.hash, &self.server_handshake_traffic, b"finished", b"", &mut server);
+        FinishedKeys {
+            client: client.into(),
+            server: server.into(),
+        }
+    }
+
+    /// Application traffic keys for both directions
+    pub fn session_keys(&self) -> SessionKeys {
+        let (k_client_to_server, nonce_base_c2s) =
+            traffic_key_iv(self.hash, &self.client_application_traffic);
+        let (k_server_to_client, nonce_base_s2c) =
+            traffic_key_iv(self.hash, &self.server_application_traffic);
+        SessionKeys {
+            k_client_to_server: *k_client_to_server,
+            k_server_to_client: *k_server_to_client,
+            nonce_base_c2s: *nonce_base_c2s,
+            nonce_base_s2c: *nonce_base_s2c,
+        }
+    }
+}
+
+/// HMAC over the transcript under a Finished key
+pub fn finished_mac(hash: HashAlgorithm, finished_key: &[u8], transcript: &[u8]) -> Vec<u8> {
+    hash.hmac(finished_key, transcript)
+}
+
+/// Check a peer's Finished MAC in constant time
+pub fn verify_finished_mac(
+    hash: HashAlgorithm,
+    finished_key: &[u8],
+    transcript: &[u8],
+    tag: &[u8],
+) -> bool {
+    hash.verify_hmac(finished_key, transcript, tag)
+}
@@ -0,0 +1,224 @@
+use std::marker::PhantomData;
+
+use rand::{CryptoRng, RngCore};
+use sha3::{Digest, Sha3_256};
+
+use crate::hkdf::{KdfHashFunction, Sha256};
+use crate::secret::SecretBytes;
+use crate::traits::{Kem, KemError};
+
+const CONCAT_HKDF_INFO: &[u8] = b"hybrid-kem concat";
+/// X-Wing domain separator, the ASCII art `\.//^\`
+const XWING_LABEL: &[u8] = b"\x5c\x2e\x2f\x2f\x5e\x5c";
+
+/// Everything a combiner may bind: both component secrets, ciphertexts and public keys
+pub struct CombinerInput<'a> {
+    pub ss_a: &'a [u8],
+    pub ss_b: &'a [u8],
+    pub ct_a: &'a [u8],
+    pub ct_b: &'a [u8],
+    pub pk_a: &'a [u8],
+    pub pk_b: &'a [u8],
+}
+
+/// Turns the two component shared secrets into the hybrid shared secret
+pub trait Combiner {
+    /// Length of the combined secret
+    const SHARED_SECRET_SIZE: usize;
+
+    fn combine(input: &CombinerInput) -> SecretBytes;
+}
+
+/// HKDF over `ss_a ‖ ss_b` under `H`, one hash length out; relies on the caller's transcript
+/// to bind ciphertexts. Suites pass their KDF hash so the combiner is never the weakest link.
+pub struct ConcatHkdf<H = Sha256> {
+    _marker: PhantomData<H>,
+}
+
+/// X-Wing combiner: SHA3-256(ss_a ‖ ss_b ‖ ct_b ‖ pk_b ‖ label), binding the classical share
+pub struct XWing;
+
+impl<H: KdfHashFunction> Combiner for ConcatHkdf<H> {
+    const SHARED_SECRET_SIZE: usize = H::ALGORITHM.output_len();
+
+    fn combine(input: &CombinerInput) -> SecretBytes {
+        let ikm = SecretBytes::from([input.ss_a, input.ss_b].concat());
+        // An empty salt is a hash length of zeros (RFC 5869 §2.2)
+        let prk = H::ALGORITHM.extract(&[], &ikm);
+        let mut ss = vec![0u8; Self::SHARED_SECRET_SIZE];
+        H::ALGORITHM.expand(&prk, CONCAT_HKDF_INFO, &mut ss);
+        SecretBytes::from(ss)
+    }
+}
+
+impl Combiner for XWing {
+    const SHARED_SECRET_SIZE: usize = 32;
+
+    fn combine(input: &CombinerInput) -> SecretBytes {
+        let mut hasher = Sha3_256::new();
+        hasher.update(input.ss_a);
+        hasher.update(input.ss_b);
+        hasher.update(input.ct_b);
+        hasher.update(input.pk_b);
+        hasher.update(XWING_LABEL);
+        SecretBytes::from(hasher.finalize().as_slice())
+    }
+}
+
+/// Two KEMs run side by side, their secrets merged by `C`; secure while either component is
+pub struct HybridKem<A, B, C> {
+    _marker: PhantomData<(A, B, C)>,
+}
+
+pub struct HybridPublicKey<A: Kem, B: Kem> {
+    pub a: A::PublicKey,
+    pub b: B::PublicKey,
+}
+
+impl<A: Kem, B: Kem> Clone for HybridPublicKey<A, B>
+where
+    A::PublicKey: Clone,
+    B::PublicKey: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            a: self.a.clone(),
+            b: self.b.clone(),
+        }
+    }
+}
+
+/// Both component secret keys, plus the public keys combiners bind on decapsulation
+pub struct HybridSecretKey<A: Kem, B: Kem> {
+    pub a: A::SecretKey,
+    pub b: B::SecretKey,
+    pub public: HybridPublicKey<A, B>,
+}
+
+pub struct HybridCiphertext<A: Kem, B: Kem> {
+    pub a: A::Ciphertext,
+    pub b: B::Ciphertext,
+}
+
+impl<A, B, C> Kem for HybridKem<A, B, C>
+where
+    A: Kem<SharedSecret = SecretBytes>,
+    B: Kem<SharedSecret = SecretBytes>,
+    A::PublicKey: Clone,
+    B::PublicKey: Clone,
+    C: Combiner,
+{
+    type PublicKey = HybridPublicKey<A, B>;
+    type SecretKey = HybridSecretKey<A, B>;
+    type Ciphertext = HybridCiphertext<A, B>;
+    type SharedSecret = SecretBytes;
+
+    const PUBLIC_KEY_SIZE: usize = A::PUBLIC_KEY_SIZE + B::PUBLIC_KEY_SIZE;
+    /// Both secret keys followed by both public keys
+    const SECRET_KEY_SIZE: usize = A::SECRET_KEY_SIZE + B::SECRET_KEY_SIZE + Self::PUBLIC_KEY_SIZE;
+    const CIPHERTEXT_SIZE: usize = A::CIPHERTEXT_SIZE + B::CIPHERTEXT_SIZE;
+    const SHARED_SECRET_SIZE: usize = C::SHARED_SECRET_SIZE;
+
+    fn generate_keypair_with_rng<R: CryptoRng + RngCore>(
+        rng: &mut R,
+    ) -> (Self::PublicKey, Self::SecretKey) {
+        let (pk_a, sk_a) = A::generate_keypair_with_rng(rng);
+        let (pk_b, sk_b) = B::generate_keypair_with_rng(rng);
+        let public = HybridPublicKey { a: pk_a, b: pk_b };
+        let secret = HybridSecretKey {
+            a: sk_a,
+            b: sk_b,
+            public: public.clone(),
+        };
+        (public, secret)
+    }
+
+    fn encapsulate_with_rng<R: CryptoRng + RngCore>(
+        pk: &Self::PublicKey,
+        rng: &mut R,
+    ) -> Result<(Self::Ciphertext, Self::SharedSecret), KemError> {
+        let (ct_a, ss_a) = A::encapsulate_with_rng(&pk.a, rng)?;
+        let (ct_b, ss_b) = B::encapsulate_with_rng(&pk.b, rng)?;
+        let ss = combine::<A, B, C>(&ss_a, &ss_b, &ct_a, &ct_b, pk);
+        Ok((HybridCiphertext { a: ct_a, b: ct_b }, ss))
+    }
+
+    fn decapsulate(sk: &Self::SecretKey, ct: &Self::Ciphertext) -> Result<Self::SharedSecret, KemError> {
+        let ss_a = A::decapsulate(&sk.a, &ct.a)?;
+        let ss_b = B::decapsulate(&sk.b, &ct.b)?;
+        Ok(combine::<A, B, C>(&ss_a, &ss_b, &ct.a, &ct.b, &sk.public))
+    }
+
+    fn public_key_to_bytes(pk: &Self::PublicKey) -> Vec<u8> {
+        [A::public_key_to_bytes(&pk.a), B::public_key_to_bytes(&pk.b)].concat()
+    }
+
+    fn public_key_from_bytes(bytes: &[u8]) -> Result<Self::PublicKey, KemError> {
+        if bytes.len() != Self::PUBLIC_KEY_SIZE {
+            return Err(KemError::InvalidPublicKey);
+        }
+        let (a, b) = bytes.split_at(A::PUBLIC_KEY_SIZE);
+        Ok(HybridPublicKey {
+            a: A::public_key_from_bytes(a)?,
+            b: B::public_key_from_bytes(b)?,
+        })
+    }
+
+    fn secret_key_to_bytes(sk: &Self::SecretKey) -> SecretBytes {
+        let mut bytes = Vec::with_capacity(Self::SECRET_KEY_SIZE);
+        bytes.extend_from_slice(&A::secret_key_to_bytes(&sk.a));
+        bytes.extend_from_slice(&B::secret_key_to_bytes(&sk.b));
+        bytes.extend_from_slice(&Self::public_key_to_bytes(&sk.public));
+        SecretBytes::from(bytes)
+    }
+
+    fn secret_key_from_bytes(bytes: &[u8]) -> Result<Self::SecretKey, KemError> {
+        if bytes.len() != Self::SECRET_KEY_SIZE {
+            return Err(KemError::InvalidSecretKey);
+        }
+        let (a, rest) = bytes.split_at(A::SECRET_KEY_SIZE);
+        let (b, public) = rest.split_at(B::SECRET_KEY_SIZE);
+        Ok(HybridSecretKey {
+            a: A::secret_key_from_bytes(a)?,
+            b: B::secret_key_from_bytes(b)?,
+            public: Self::public_key_from_bytes(public).map_err(|_| KemError::InvalidSecretKey)?,
+        })
+    }
+
+    fn ciphertext_to_bytes(ct: &Self::Ciphertext) -> Vec<u8> {
+        [A::ciphertext_to_bytes(&ct.a), B::ciphertext_to_bytes(&ct.b)].concat()
+    }
+
+    fn ciphertext_from_bytes(bytes: &[u8]) -> Result<Self::Ciphertext, KemError> {
+        if bytes.len() != Self::CIPHERTEXT_SIZE {
+            return Err(KemError::InvalidCiphertext);
+        }
+        let (a, b) = bytes.split_at(A::CIPHERTEXT_SIZE);
+        Ok(HybridCiphertext {
+            a: A::ciphertext_from_bytes(a)?,
+            b: B::ciphertext_from_bytes(b)?,
+        })
+    }
+}
+
+fn combine<A, B, C>(
+    ss_a: &[u8],
+    ss_b: &[u8],
+    ct_a: &A::Ciphertext,
+    ct_b: &B::Ciphertext,
+    pk: &HybridPublicKey<A, B>,
+) -> SecretBytes
+where
+    A: Kem,
+    B: Kem,
+    C: Combiner,
+{
+    C::combine(&CombinerInput {
+        ss_a,
+        ss_b,
+        ct_a: &A::ciphertext_to_bytes(ct_a),
+        ct_b: &B::ciphertext_to_bytes(ct_b),
+        pk_a: &A::public_key_to_bytes(&pk.a),
+        pk_b: &B::public_key_to_bytes(&pk.b),
+    })
+}
@@ -1,27 +1,298 @@
-use pqcrypto_kyber::kyber768;
-use pqcrypto_traits::kem::SharedSecret as SharedSecretTrait;
+use pqcrypto_kyber::{kyber1024, kyber512, kyber768};
+use pqcrypto_traits::kem::{
+    Ciphertext, PublicKey, SecretKey, SharedSecret as SharedSecretTrait,
+};
+use ml_kem::kem::Decapsulate;
+use ml_kem::{EncapsulateDeterministic, EncodedSizeUser, KemCore, MlKem1024, MlKem512, MlKem768};
+use rand::{CryptoRng, RngCore};
+use zeroize::{Zeroize, Zeroizing};
 
-use crate::traits::Kem;
+use crate::secret::SecretBytes;
+use crate::traits::{Kem, KemError};
 
+const Q: u16 = 3329;
+
+/// Round-3 CRYSTALS-Kyber512 (PQClean, through pqcrypto-kyber)
+pub struct Kyber512Kem;
+
+/// Round-3 CRYSTALS-Kyber768 (PQClean, through pqcrypto-kyber)
 pub struct Kyber768Kem;
 
-impl Kem for Kyber768Kem {
-    type PublicKey = kyber768::PublicKey;
-    type SecretKey = kyber768::SecretKey;
-    type Ciphertext = kyber768::Ciphertext;
-    type SharedSecret = Vec<u8>;
+/// Round-3 CRYSTALS-Kyber1024 (PQClean, through pqcrypto-kyber)
+pub struct Kyber1024Kem;
 
-    fn generate_keypair() -> (Self::PublicKey, Self::SecretKey) {
-        kyber768::keypair()
-    }
+/// FIPS 203 ML-KEM-512 (RustCrypto `ml-kem`)
+pub struct MlKem512Kem;
 
-    fn encapsulate(pk: &Self::PublicKey) -> (Self::Ciphertext, Self::SharedSecret) {
-        let (ss, ct) = kyber768::encapsulate(pk);
-        (ct, ss.as_bytes().to_vec())
-    }
+/// FIPS 203 ML-KEM-768
+pub struct MlKem768Kem;
+
+/// FIPS 203 ML-KEM-1024
+pub struct MlKem1024Kem;
 
-    fn decapsulate(sk: &Self::SecretKey, ct: &Self::Ciphertext) -> Self::SharedSecret {
-        let ss = kyber768::decapsulate(ct, sk);
-        ss.as_bytes().to_vec()
+/// Encapsulation key modulus check (FIPS 203, §7.2): every 12-bit coefficient of `t_hat`
+/// is below q. The key is public, so the early exit leaks nothing.
+fn public_key_is_reduced(pk: &[u8], k: usize) -> bool {
+    pk[..384 * k].chunks_exact(3).all(|c| {
+        let lo = c[0] as u16 | ((c[1] as u16 & 0x0f) << 8);
+        let hi = (c[1] as u16 >> 4) | ((c[2] as u16) << 4);
+        lo < Q && hi < Q
+    })
+}
+
+/// PQClean's deterministic Kyber entry points. pqcrypto-kyber compiles and links them as
+/// part of the clean implementation, but only binds the wrappers that fill the coins from
+/// `randombytes`; these take the coins from the caller instead.
+mod pqclean {
+    use core::ffi::c_int;
+
+    unsafe extern "C" {
+        pub fn PQCLEAN_KYBER512_CLEAN_crypto_kem_keypair_derand(
+            pk: *mut u8,
+            sk: *mut u8,
+            coins: *const u8,
+        ) -> c_int;
+        pub fn PQCLEAN_KYBER512_CLEAN_crypto_kem_enc_derand(
+            ct: *mut u8,
+            ss: *mut u8,
+            pk: *const u8,
+            coins: *const u8,
+        ) -> c_int;
+        pub fn PQCLEAN_KYBER768_CLEAN_crypto_kem_keypair_derand(
+            pk: *mut u8,
+            sk: *mut u8,
+            coins: *const u8,
+        ) -> c_int;
+        pub fn PQCLEAN_KYBER768_CLEAN_crypto_kem_enc_derand(
+            ct: *mut u8,
+            ss: *mut u8,
+            pk: *const u8,
+            coins: *const u8,
+        ) -> c_int;
+        pub fn PQCLEAN_KYBER1024_CLEAN_crypto_kem_keypair_derand(
+            pk: *mut u8,
+            sk: *mut u8,
+            coins: *const u8,
+        ) -> c_int;
+        pub fn PQCLEAN_KYBER1024_CLEAN_crypto_kem_enc_derand(
+            ct: *mut u8,
+            ss: *mut u8,
+            pk: *const u8,
+            coins: *const u8,
+        ) -> c_int;
     }
 }
+
+/// Kyber over PQClean. Secret keys are kept in `SecretBytes`, since pqcrypto's own key
+/// type is `Copy` and never wiped; it is only rebuilt for the duration of a decapsulation.
+///
+/// Key generation and encapsulation go through PQClean's `_derand` functions with coins
+/// drawn from the caller's RNG, 64 bytes (`d ‖ z`) and then 32, the way PQClean's own
+/// wrappers draw them, so a seeded RNG reproduces the round-3 NIST KAT.
+macro_rules! impl_pqclean_kem {
+    (
+        $kem:ty, $module:ident, $keypair:ident, $enc:ident,
+        $k:expr, $pk_size:expr, $sk_size:expr, $ct_size:expr
+    ) => {
+        impl Kem for $kem {
+            type PublicKey = $module::PublicKey;
+            type SecretKey = SecretBytes;
+            type Ciphertext = $module::Ciphertext;
+            type SharedSecret = SecretBytes;
+
+            const PUBLIC_KEY_SIZE: usize = $pk_size;
+            const SECRET_KEY_SIZE: usize = $sk_size;
+            const CIPHERTEXT_SIZE: usize = $ct_size;
+            const SHARED_SECRET_SIZE: usize = 32;
+
+            fn generate_keypair_with_rng<R: CryptoRng + RngCore>(
+                rng: &mut R,
+            ) -> (Self::PublicKey, Self::SecretKey) {
+                let mut coins = Zeroizing::new([0u8; 64]);
+                rng.fill_bytes(coins.as_mut());
+                let mut pk = [0u8; $pk_size];
+                let mut sk = Zeroizing::new([0u8; $sk_size]);
+                // SAFETY: the buffers have the lengths the parameter set's `kem.h` declares
+                // for pk, sk and the 2 * KYBER_SYMBYTES coins
+                unsafe { pqclean::$keypair(pk.as_mut_ptr(), sk.as_mut_ptr(), coins.as_ptr()) };
+                let pk = $module::PublicKey::from_bytes(&pk).expect("PQClean public key size");
+                (pk, SecretBytes::from(&sk[..]))
+            }
+
+            fn encapsulate_with_rng<R: CryptoRng + RngCore>(
+                pk: &Self::PublicKey,
+                rng: &mut R,
+            ) -> Result<(Self::Ciphertext, Self::SharedSecret), KemError> {
+                // Required by FIPS 203; honest Kyber keys are reduced too, so both variants check
+                if !public_key_is_reduced(pk.as_bytes(), $k) {
+                    return Err(KemError::ModulusCheckFailed);
+                }
+                let mut coins = Zeroizing::new([0u8; 32]);
+                rng.fill_bytes(coins.as_mut());
+                let mut ct = [0u8; $ct_size];
+                let mut ss = Zeroizing::new([0u8; 32]);
+                // SAFETY: `pk` is a PQClean public key of this parameter set, and the other
+                // buffers have the ct, ss and KYBER_SYMBYTES coin lengths `kem.h` declares
+                unsafe {
+                    pqclean::$enc(
+                        ct.as_mut_ptr(),
+                        ss.as_mut_ptr(),
+                        pk.as_bytes().as_ptr(),
+                        coins.as_ptr(),
+                    )
+                };
+                let ct = $module::Ciphertext::from_bytes(&ct).expect("PQClean ciphertext size");
+                Ok((ct, SecretBytes::from(&ss[..])))
+            }
+
+            fn decapsulate(
+                sk: &Self::SecretKey,
+                ct: &Self::Ciphertext,
+            ) -> Result<Self::SharedSecret, KemError> {
+                let sk = $module::SecretKey::from_bytes(sk).map_err(|_| KemError::InvalidSecretKey)?;
+                Ok(SecretBytes::from($module::decapsulate(ct, &sk).as_bytes()))
+            }
+
+            fn public_key_to_bytes(pk: &Self::PublicKey) -> Vec<u8> {
+                pk.as_bytes().to_vec()
+            }
+
+            fn public_key_from_bytes(bytes: &[u8]) -> Result<Self::PublicKey, KemError> {
+                PublicKey::from_bytes(bytes).map_err(|_| KemError::InvalidPublicKey)
+            }
+
+            fn secret_key_to_bytes(sk: &Self::SecretKey) -> SecretBytes {
+                sk.clone()
+            }
+
+            fn secret_key_from_bytes(bytes: &[u8]) -> Result<Self::SecretKey, KemError> {
+                if bytes.len() != $sk_size {
+                    return Err(KemError::InvalidSecretKey);
+                }
+                Ok(SecretBytes::from(bytes))
+            }
+
+            fn ciphertext_to_bytes(ct: &Self::Ciphertext) -> Vec<u8> {
+                ct.as_bytes().to_vec()
+            }
+
+            fn ciphertext_from_bytes(bytes: &[u8]) -> Result<Self::Ciphertext, KemError> {
+                Ciphertext::from_bytes(bytes).map_err(|_| KemError::InvalidCiphertext)
+            }
+        }
+    };
+}
+
+/// ML-KEM over RustCrypto's `ml-kem`, which reduces with Barrett multiplication and
+/// compresses with a multiply-shift, so no secret-dependent division reaches the CPU.
+///
+/// Randomness is drawn the way the FIPS 203 reference does it (`d ‖ z` in one 64-byte
+/// draw, then a 32-byte `m`), so a seeded RNG reproduces the NIST KAT records.
+macro_rules! impl_mlkem {
+    ($kem:ty, $backend:ty, $k:expr, $pk_size:expr, $sk_size:expr, $ct_size:expr) => {
+        impl Kem for $kem {
+            type PublicKey = <$backend as KemCore>::EncapsulationKey;
+            type SecretKey = SecretBytes;
+            type Ciphertext = ml_kem::Ciphertext<$backend>;
+            type SharedSecret = SecretBytes;
+
+            const PUBLIC_KEY_SIZE: usize = $pk_size;
+            const SECRET_KEY_SIZE: usize = $sk_size;
+            const CIPHERTEXT_SIZE: usize = $ct_size;
+            const SHARED_SECRET_SIZE: usize = 32;
+
+            fn generate_keypair_with_rng<R: CryptoRng + RngCore>(
+                rng: &mut R,
+            ) -> (Self::PublicKey, Self::SecretKey) {
+                let mut seed = Zeroizing::new([0u8; 64]);
+                rng.fill_bytes(seed.as_mut());
+                let (d, z) = seed.split_at(32);
+                let (dk, ek) = <$backend>::generate_deterministic(
+                    d.try_into().expect("32-byte d"),
+                    z.try_into().expect("32-byte z"),
+                );
+                let mut encoded = dk.as_bytes();
+                let sk = SecretBytes::from(&encoded[..]);
+                encoded[..].zeroize();
+                (ek, sk)
+            }
+
+            fn encapsulate_with_rng<R: CryptoRng + RngCore>(
+                pk: &Self::PublicKey,
+                rng: &mut R,
+            ) -> Result<(Self::Ciphertext, Self::SharedSecret), KemError> {
+                let mut m = Zeroizing::new([0u8; 32]);
+                rng.fill_bytes(m.as_mut());
+                let (ct, mut ss) = pk
+                    .encapsulate_deterministic((&m[..]).try_into().expect("32-byte m"))
+                    .map_err(|_| KemError::EncapsulationFailed)?;
+                let shared_secret = SecretBytes::from(&ss[..]);
+                ss[..].zeroize();
+                Ok((ct, shared_secret))
+            }
+
+            fn decapsulate(
+                sk: &Self::SecretKey,
+                ct: &Self::Ciphertext,
+            ) -> Result<Self::SharedSecret, KemError> {
+                let encoded = sk[..].try_into().map_err(|_| KemError::InvalidSecretKey)?;
+                let dk = <<$backend as KemCore>::DecapsulationKey>::from_bytes(encoded);
+                let mut ss = dk.decapsulate(ct).map_err(|_| KemError::DecapsulationFailed)?;
+                let shared_secret = SecretBytes::from(&ss[..]);
+                ss[..].zeroize();
+                Ok(shared_secret)
+            }
+
+            fn public_key_to_bytes(pk: &Self::PublicKey) -> Vec<u8> {
+                pk.as_bytes().to_vec()
+            }
+
+            fn public_key_from_bytes(bytes: &[u8]) -> Result<Self::PublicKey, KemError> {
+                let encoded = bytes.try_into().map_err(|_| KemError::InvalidPublicKey)?;
+                // Decoding reduces every coefficient, so the check has to see the raw bytes
+                if !public_key_is_reduced(bytes, $k) {
+                    return Err(KemError::ModulusCheckFailed);
+                }
+                Ok(Self::PublicKey::from_bytes(encoded))
+            }
+
+            fn secret_key_to_bytes(sk: &Self::SecretKey) -> SecretBytes {
+                sk.clone()
+            }
+
+            fn secret_key_from_bytes(bytes: &[u8]) -> Result<Self::SecretKey, KemError> {
+                if bytes.len() != $sk_size {
+                    return Err(KemError::InvalidSecretKey);
+                }
+                Ok(SecretBytes::from(bytes))
+            }
+
+            fn ciphertext_to_bytes(ct: &Self::Ciphertext) -> Vec<u8> {
+                ct.to_vec()
+            }
+
+            fn ciphertext_from_bytes(bytes: &[u8]) -> Result<Self::Ciphertext, KemError> {
+                bytes.try_into().map_err(|_| KemError::InvalidCiphertext)
+            }
+        }
+    };
+}
+
+impl_pqclean_kem!(
+    Kyber512Kem, kyber512,
+    PQCLEAN_KYBER512_CLEAN_crypto_kem_keypair_derand, PQCLEAN_KYBER512_CLEAN_crypto_kem_enc_derand,
+    2, 800, 1632, 768
+);
+impl_pqclean_kem!(
+    Kyber768Kem, kyber768,
+    PQCLEAN_KYBER768_CLEAN_crypto_kem_keypair_derand, PQCLEAN_KYBER768_CLEAN_crypto_kem_enc_derand,
+    3, 1184, 2400, 1088
+);
+impl_pqclean_kem!(
+    Kyber1024Kem, kyber1024,
+    PQCLEAN_KYBER1024_CLEAN_crypto_kem_keypair_derand, PQCLEAN_KYBER1024_CLEAN_crypto_kem_enc_derand,
+    4, 1568, 3168, 1568
+);
+impl_mlkem!(MlKem512Kem, MlKem512, 2, 800, 1632, 768);
+impl_mlkem!(MlKem768Kem, MlKem768, 3, 1184, 2400, 1088);
+impl_mlkem!(MlKem1024Kem, MlKem1024, 4, 1568, 3168, 1568);
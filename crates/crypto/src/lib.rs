@@ -1,5 +1,10 @@
 pub mod aead;
+pub mod dhkem;
 pub mod hkdf;
+pub mod hybrid;
 pub mod kyber;
+pub mod p256;
+pub mod secret;
 pub mod traits;
 pub mod x25519;
+pub mod x448;
@@ -0,0 +1,60 @@
+use ::p256::ecdh::diffie_hellman;
+use ::p256::elliptic_curve::sec1::ToEncodedPoint;
+use ::p256::{PublicKey, SecretKey};
+use rand::{CryptoRng, RngCore};
+
+use crate::secret::SecretBytes;
+use crate::traits::Dh;
+
+/// Uncompressed SEC1 point: 0x04 ‖ x ‖ y
+const UNCOMPRESSED_POINT_LEN: usize = 65;
+
+/// NIST P-256 ECDH (SP 800-56A) with uncompressed public keys, as TLS 1.3 uses it
+pub struct P256Dh;
+
+impl Dh for P256Dh {
+    type SecretKey = SecretKey;
+    type PublicKey = PublicKey;
+    type SharedSecret = SecretBytes;
+
+    const PUBLIC_KEY_SIZE: usize = UNCOMPRESSED_POINT_LEN;
+    const SECRET_KEY_SIZE: usize = 32;
+    const SHARED_SECRET_SIZE: usize = 32;
+
+    fn generate_keypair_with_rng<R: CryptoRng + RngCore>(
+        rng: &mut R,
+    ) -> (Self::PublicKey, Self::SecretKey) {
+        let sk = SecretKey::random(rng);
+        (sk.public_key(), sk)
+    }
+
+    fn public_key(sk: &Self::SecretKey) -> Self::PublicKey {
+        sk.public_key()
+    }
+
+    /// The x-coordinate of the shared point
+    fn diffie_hellman(sk: &Self::SecretKey, pk: &Self::PublicKey) -> Self::SharedSecret {
+        let shared = diffie_hellman(sk.to_nonzero_scalar(), pk.as_affine());
+        SecretBytes::from(shared.raw_secret_bytes().as_slice())
+    }
+
+    fn public_key_to_bytes(pk: &Self::PublicKey) -> Vec<u8> {
+        pk.to_encoded_point(false).as_bytes().to_vec()
+    }
+
+    /// Only uncompressed points are accepted; the point must be on the curve
+    fn public_key_from_bytes(bytes: &[u8]) -> Option<Self::PublicKey> {
+        if bytes.len() != UNCOMPRESSED_POINT_LEN {
+            return None;
+        }
+        PublicKey::from_sec1_bytes(bytes).ok()
+    }
+
+    fn secret_key_to_bytes(sk: &Self::SecretKey) -> SecretBytes {
+        SecretBytes::from(sk.to_bytes().as_slice())
+    }
+
+    fn secret_key_from_bytes(bytes: &[u8]) -> Option<Self::SecretKey> {
+        SecretKey::from_slice(bytes).ok()
+    }
+}
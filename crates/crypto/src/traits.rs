@@ -1,17 +1,83 @@
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
+use thiserror::Error;
+
+use crate::aead::AeadError;
+use crate::secret::SecretBytes;
+
+/// Why a KEM operation or key/ciphertext decoding failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum KemError {
+    #[error("invalid public key encoding")]
+    InvalidPublicKey,
+    #[error("invalid secret key encoding")]
+    InvalidSecretKey,
+    #[error("invalid ciphertext encoding")]
+    InvalidCiphertext,
+    #[error("encapsulation failed")]
+    EncapsulationFailed,
+    #[error("decapsulation failed")]
+    DecapsulationFailed,
+    /// An ML-KEM public key coefficient is not reduced mod q (FIPS 203 §7.2)
+    #[error("public key coefficient not reduced mod q")]
+    ModulusCheckFailed,
+    /// The DH output is all zeros: the peer's point has small order (RFC 7748 §6.1)
+    #[error("peer key share has small order")]
+    NonContributory,
+}
+
 pub trait Kem {
     type PublicKey;
     type SecretKey;
     type Ciphertext;
     type SharedSecret;
 
-    /// Generate a new Kyber768 keypair
-    fn generate_keypair() -> (Self::PublicKey, Self::SecretKey);
+    const PUBLIC_KEY_SIZE: usize;
+    const SECRET_KEY_SIZE: usize;
+    const CIPHERTEXT_SIZE: usize;
+    const SHARED_SECRET_SIZE: usize;
+
+    /// Generate a new keypair from the caller's randomness
+    fn generate_keypair_with_rng<R: CryptoRng + RngCore>(
+        rng: &mut R,
+    ) -> (Self::PublicKey, Self::SecretKey);
+
+    /// Generate a new keypair
+    fn generate_keypair() -> (Self::PublicKey, Self::SecretKey) {
+        Self::generate_keypair_with_rng(&mut OsRng)
+    }
+
+    /// Encapsulate a shared secret to a public key, drawing randomness from `rng`
+    fn encapsulate_with_rng<R: CryptoRng + RngCore>(
+        pk: &Self::PublicKey,
+        rng: &mut R,
+    ) -> Result<(Self::Ciphertext, Self::SharedSecret), KemError>;
 
     /// Encapsulate a shared secret to a public key
-    fn encapsulate(pk: &Self::PublicKey) -> (Self::Ciphertext, Self::SharedSecret);
+    fn encapsulate(pk: &Self::PublicKey) -> Result<(Self::Ciphertext, Self::SharedSecret), KemError> {
+        Self::encapsulate_with_rng(pk, &mut OsRng)
+    }
 
     /// Decapsulate a ciphertext using a secret key
-    fn decapsulate(sk: &Self::SecretKey, ct: &Self::Ciphertext) -> Self::SharedSecret;
+    fn decapsulate(sk: &Self::SecretKey, ct: &Self::Ciphertext) -> Result<Self::SharedSecret, KemError>;
+
+    /// Wire encoding of a public key
+    fn public_key_to_bytes(pk: &Self::PublicKey) -> Vec<u8>;
+
+    /// Parse a peer's public key, rejecting a wrong size or an invalid encoding
+    fn public_key_from_bytes(bytes: &[u8]) -> Result<Self::PublicKey, KemError>;
+
+    /// Encoding of a secret key, wiped when dropped
+    fn secret_key_to_bytes(sk: &Self::SecretKey) -> SecretBytes;
+
+    /// Parse a secret key written by `secret_key_to_bytes`
+    fn secret_key_from_bytes(bytes: &[u8]) -> Result<Self::SecretKey, KemError>;
+
+    /// Wire encoding of a ciphertext
+    fn ciphertext_to_bytes(ct: &Self::Ciphertext) -> Vec<u8>;
+
+    /// Parse a peer's ciphertext, rejecting a wrong size or an invalid encoding
+    fn ciphertext_from_bytes(bytes: &[u8]) -> Result<Self::Ciphertext, KemError>;
 }
 
 pub trait Dh {
@@ -19,12 +85,52 @@ pub trait Dh {
     type PublicKey;
     type SharedSecret;
 
+    const PUBLIC_KEY_SIZE: usize;
+    const SECRET_KEY_SIZE: usize;
+    const SHARED_SECRET_SIZE: usize;
+
+    /// Generate a new keypair from the caller's randomness
+    fn generate_keypair_with_rng<R: CryptoRng + RngCore>(
+        rng: &mut R,
+    ) -> (Self::PublicKey, Self::SecretKey);
+
     /// Generate a new keypair
-    fn generate_keypair() -> (Self::PublicKey, Self::SecretKey);
+    fn generate_keypair() -> (Self::PublicKey, Self::SecretKey) {
+        Self::generate_keypair_with_rng(&mut OsRng)
+    }
 
     /// Derive the public key from a secret key
     fn public_key(sk: &Self::SecretKey) -> Self::PublicKey;
 
     /// Perform a Diffie-Hellman exchange
     fn diffie_hellman(sk: &Self::SecretKey, pk: &Self::PublicKey) -> Self::SharedSecret;
+
+    /// Wire encoding of a public key
+    fn public_key_to_bytes(pk: &Self::PublicKey) -> Vec<u8>;
+
+    /// Parse a peer's public key, or `None` if the encoding is invalid
+    fn public_key_from_bytes(bytes: &[u8]) -> Option<Self::PublicKey>;
+
+    fn secret_key_to_bytes(sk: &Self::SecretKey) -> SecretBytes;
+    fn secret_key_from_bytes(bytes: &[u8]) -> Option<Self::SecretKey>;
+}
+
+pub trait Aead {
+    /// Encrypt under the per-message nonce derived from `nonce_base` and `seq`
+    fn encrypt(
+        key: &[u8; 32],
+        nonce_base: &[u8; 12],
+        seq: u64,
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> Vec<u8>;
+
+    /// Decrypt and authenticate a ciphertext with its appended tag
+    fn decrypt(
+        key: &[u8; 32],
+        nonce_base: &[u8; 12],
+        seq: u64,
+        aad: &[u8],
+        ciphertext_with_tag: &[u8],
+    ) -> Result<Vec<u8>, AeadError>;
 }
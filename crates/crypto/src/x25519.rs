@@ -1,48 +1,138 @@
 use hkdf::Hkdf;
-use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
 use sha2::Sha256;
 use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroize;
 
-use crate::traits::Kem;
+use crate::secret::SecretBytes;
+use crate::traits::{Dh, Kem, KemError};
 
 const HKDF_INFO: &[u8] = b"x25519-kem";
 
 pub struct X25519Kem;
 
+/// X25519 Diffie-Hellman (RFC 7748)
+pub struct X25519Dh;
+
 impl Kem for X25519Kem {
     type PublicKey = PublicKey;
     type SecretKey = StaticSecret;
     type Ciphertext = [u8; 32];
-    type SharedSecret = Vec<u8>;
+    type SharedSecret = SecretBytes;
+
+    const PUBLIC_KEY_SIZE: usize = 32;
+    const SECRET_KEY_SIZE: usize = 32;
+    const CIPHERTEXT_SIZE: usize = 32;
+    const SHARED_SECRET_SIZE: usize = 32;
 
-    fn generate_keypair() -> (Self::PublicKey, Self::SecretKey) {
-        let sk = StaticSecret::random_from_rng(OsRng);
+    fn generate_keypair_with_rng<R: CryptoRng + RngCore>(
+        rng: &mut R,
+    ) -> (Self::PublicKey, Self::SecretKey) {
+        let sk = StaticSecret::random_from_rng(rng);
         let pk = PublicKey::from(&sk);
         (pk, sk)
     }
 
-    fn encapsulate(pk: &Self::PublicKey) -> (Self::Ciphertext, Self::SharedSecret) {
-        let eph_sk = StaticSecret::random_from_rng(OsRng);
+    fn encapsulate_with_rng<R: CryptoRng + RngCore>(
+        pk: &Self::PublicKey,
+        rng: &mut R,
+    ) -> Result<(Self::Ciphertext, Self::SharedSecret), KemError> {
+        let eph_sk = StaticSecret::random_from_rng(rng);
         let eph_pk = PublicKey::from(&eph_sk);
         let dh = eph_sk.diffie_hellman(pk);
+        if !dh.was_contributory() {
+            return Err(KemError::NonContributory);
+        }
 
         let mut okm = vec![0u8; 32];
         let hk = Hkdf::<Sha256>::new(None, dh.as_bytes());
         hk.expand(HKDF_INFO, &mut okm)
             .expect("valid output length");
 
-        (eph_pk.to_bytes(), okm)
+        Ok((eph_pk.to_bytes(), SecretBytes::from(okm)))
     }
 
-    fn decapsulate(sk: &Self::SecretKey, ct: &Self::Ciphertext) -> Self::SharedSecret {
+    fn decapsulate(sk: &Self::SecretKey, ct: &Self::Ciphertext) -> Result<Self::SharedSecret, KemError> {
         let eph_pk = PublicKey::from(*ct);
         let dh = sk.diffie_hellman(&eph_pk);
+        if !dh.was_contributory() {
+            return Err(KemError::NonContributory);
+        }
 
         let mut okm = vec![0u8; 32];
         let hk = Hkdf::<Sha256>::new(None, dh.as_bytes());
         hk.expand(HKDF_INFO, &mut okm)
             .expect("valid output length");
 
-        okm
+        Ok(SecretBytes::from(okm))
+    }
+
+    fn public_key_to_bytes(pk: &Self::PublicKey) -> Vec<u8> {
+        pk.as_bytes().to_vec()
+    }
+
+    fn public_key_from_bytes(bytes: &[u8]) -> Result<Self::PublicKey, KemError> {
+        X25519Dh::public_key_from_bytes(bytes).ok_or(KemError::InvalidPublicKey)
+    }
+
+    fn secret_key_to_bytes(sk: &Self::SecretKey) -> SecretBytes {
+        SecretBytes::from(&sk.as_bytes()[..])
+    }
+
+    fn secret_key_from_bytes(bytes: &[u8]) -> Result<Self::SecretKey, KemError> {
+        X25519Dh::secret_key_from_bytes(bytes).ok_or(KemError::InvalidSecretKey)
+    }
+
+    fn ciphertext_to_bytes(ct: &Self::Ciphertext) -> Vec<u8> {
+        ct.to_vec()
+    }
+
+    fn ciphertext_from_bytes(bytes: &[u8]) -> Result<Self::Ciphertext, KemError> {
+        bytes.try_into().map_err(|_| KemError::InvalidCiphertext)
+    }
+}
+
+impl Dh for X25519Dh {
+    type SecretKey = StaticSecret;
+    type PublicKey = PublicKey;
+    type SharedSecret = SecretBytes;
+
+    const PUBLIC_KEY_SIZE: usize = 32;
+    const SECRET_KEY_SIZE: usize = 32;
+    const SHARED_SECRET_SIZE: usize = 32;
+
+    fn generate_keypair_with_rng<R: CryptoRng + RngCore>(
+        rng: &mut R,
+    ) -> (Self::PublicKey, Self::SecretKey) {
+        let sk = StaticSecret::random_from_rng(rng);
+        (PublicKey::from(&sk), sk)
+    }
+
+    fn public_key(sk: &Self::SecretKey) -> Self::PublicKey {
+        PublicKey::from(sk)
+    }
+
+    fn diffie_hellman(sk: &Self::SecretKey, pk: &Self::PublicKey) -> Self::SharedSecret {
+        SecretBytes::from(&sk.diffie_hellman(pk).as_bytes()[..])
+    }
+
+    fn public_key_to_bytes(pk: &Self::PublicKey) -> Vec<u8> {
+        pk.as_bytes().to_vec()
+    }
+
+    fn public_key_from_bytes(bytes: &[u8]) -> Option<Self::PublicKey> {
+        let bytes: [u8; 32] = bytes.try_into().ok()?;
+        Some(PublicKey::from(bytes))
+    }
+
+    fn secret_key_to_bytes(sk: &Self::SecretKey) -> SecretBytes {
+        SecretBytes::from(&sk.as_bytes()[..])
+    }
+
+    fn secret_key_from_bytes(bytes: &[u8]) -> Option<Self::SecretKey> {
+        let mut bytes: [u8; 32] = bytes.try_into().ok()?;
+        let sk = StaticSecret::from(bytes);
+        bytes.zeroize();
+        Some(sk)
     }
 }
@@ -0,0 +1,93 @@
+//! X448 (RFC 7748) over the `x448` crate.
+
+use std::fmt;
+
+use rand::{CryptoRng, RngCore};
+use x448::x448_unchecked;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::secret::SecretBytes;
+use crate::traits::Dh;
+
+pub const KEY_LEN: usize = 56;
+
+/// The u-coordinate of the base point
+pub const BASEPOINT: [u8; KEY_LEN] = {
+    let mut u = [0u8; KEY_LEN];
+    u[0] = 5;
+    u
+};
+
+/// The X448 function of RFC 7748 §5: clamps `scalar` and runs the Montgomery ladder over
+/// `u`, accepting non-canonical coordinates. The ladder is the `x448` crate's, over
+/// ed448-goldilocks field arithmetic; low-order inputs are left to `DhKem` to reject.
+pub fn x448(scalar: &[u8; KEY_LEN], u: &[u8; KEY_LEN]) -> [u8; KEY_LEN] {
+    x448_unchecked(*scalar, *u)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct X448PublicKey(pub [u8; KEY_LEN]);
+
+#[derive(Clone)]
+pub struct X448SecretKey([u8; KEY_LEN]);
+
+impl Drop for X448SecretKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for X448SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("X448SecretKey([REDACTED])")
+    }
+}
+
+/// X448 Diffie-Hellman (RFC 7748), for a higher classical margin than X25519
+pub struct X448Dh;
+
+impl Dh for X448Dh {
+    type SecretKey = X448SecretKey;
+    type PublicKey = X448PublicKey;
+    type SharedSecret = SecretBytes;
+
+    const PUBLIC_KEY_SIZE: usize = KEY_LEN;
+    const SECRET_KEY_SIZE: usize = KEY_LEN;
+    const SHARED_SECRET_SIZE: usize = KEY_LEN;
+
+    fn generate_keypair_with_rng<R: CryptoRng + RngCore>(
+        rng: &mut R,
+    ) -> (Self::PublicKey, Self::SecretKey) {
+        let mut sk = Zeroizing::new([0u8; KEY_LEN]);
+        rng.fill_bytes(&mut *sk);
+        let sk = X448SecretKey(*sk);
+        (Self::public_key(&sk), sk)
+    }
+
+    fn public_key(sk: &Self::SecretKey) -> Self::PublicKey {
+        X448PublicKey(x448(&sk.0, &BASEPOINT))
+    }
+
+    fn diffie_hellman(sk: &Self::SecretKey, pk: &Self::PublicKey) -> Self::SharedSecret {
+        let mut shared = x448(&sk.0, &pk.0);
+        let secret = SecretBytes::from(&shared[..]);
+        shared.zeroize();
+        secret
+    }
+
+    fn public_key_to_bytes(pk: &Self::PublicKey) -> Vec<u8> {
+        pk.0.to_vec()
+    }
+
+    fn public_key_from_bytes(bytes: &[u8]) -> Option<Self::PublicKey> {
+        Some(X448PublicKey(bytes.try_into().ok()?))
+    }
+
+    fn secret_key_to_bytes(sk: &Self::SecretKey) -> SecretBytes {
+        SecretBytes::from(&sk.0[..])
+    }
+
+    fn secret_key_from_bytes(bytes: &[u8]) -> Option<Self::SecretKey> {
+        Some(X448SecretKey(bytes.try_into().ok()?))
+    }
+}
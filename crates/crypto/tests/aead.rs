@@ -1,4 +1,5 @@
-use hybrid_kyber_crypto::aead::{decrypt, encrypt};
+use hybrid_kyber_crypto::aead::{decrypt, encrypt, Aes256GcmAead, ChaCha20Poly1305Aead};
+use hybrid_kyber_crypto::traits::Aead;
 
 #[test]
 fn test_encrypt_decrypt_roundtrip() {
@@ -41,3 +42,32 @@ fn test_tampered_ciphertext_fails() {
     let result = decrypt(&key, &nonce_base, seq, aad, &ciphertext);
     assert!(result.is_err());
 }
+
+#[test]
+fn test_aes256gcm_roundtrip() {
+    let key = [0x42u8; 32];
+    let nonce_base = [0x01u8; 12];
+    let seq = 7u64;
+    let aad = b"session-context";
+    let plaintext = b"hello, AES-NI world!";
+
+    let ciphertext = Aes256GcmAead::encrypt(&key, &nonce_base, seq, aad, plaintext);
+    let decrypted = Aes256GcmAead::decrypt(&key, &nonce_base, seq, aad, &ciphertext).unwrap();
+
+    assert_eq!(decrypted, plaintext);
+    assert!(Aes256GcmAead::decrypt(&key, &nonce_base, seq + 1, aad, &ciphertext).is_err());
+}
+
+#[test]
+fn test_ciphers_are_not_interchangeable() {
+    let key = [0x42u8; 32];
+    let nonce_base = [0x01u8; 12];
+    let aad = b"session-context";
+    let plaintext = b"secret message";
+
+    let ciphertext = ChaCha20Poly1305Aead::encrypt(&key, &nonce_base, 1, aad, plaintext);
+    assert_eq!(ciphertext, encrypt(&key, &nonce_base, 1, aad, plaintext));
+
+    let result = Aes256GcmAead::decrypt(&key, &nonce_base, 1, aad, &ciphertext);
+    assert!(result.is_err());
+}
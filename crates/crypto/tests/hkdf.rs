@@ -1,4 +1,9 @@
-use hybrid_kyber_crypto::hkdf::derive_session_keys;
+use hybrid_kyber_crypto::hkdf::{
+    derive_session_keys, derive_session_keys_with_hash, hkdf_expand_label, next_traffic_secret,
+    traffic_key_iv, HashAlgorithm, KeySchedule,
+};
+
+const SHA256: HashAlgorithm = HashAlgorithm::Sha256;
 
 #[test]
 fn test_derive_session_keys() {
@@ -20,3 +25,118 @@ fn test_derive_session_keys() {
     let keys3 = derive_session_keys(&ss_pq, &ss_classical, &different_transcript);
     assert_ne!(keys.k_client_to_server, keys3.k_client_to_server);
 }
+
+#[test]
+fn test_derive_session_keys_with_hash() {
+    let keys = derive_session_keys(&[1u8; 32], &[2u8; 32], &[3u8; 32]);
+    let sha256 = derive_session_keys_with_hash(SHA256, &[1u8; 32], &[2u8; 32], &[3u8; 32]);
+    assert_eq!(keys.k_client_to_server, sha256.k_client_to_server);
+    assert_eq!(keys.nonce_base_s2c, sha256.nonce_base_s2c);
+
+    let sha384 =
+        derive_session_keys_with_hash(HashAlgorithm::Sha384, &[1u8; 32], &[2u8; 32], &[3u8; 48]);
+    assert_ne!(sha384.k_client_to_server, keys.k_client_to_server);
+}
+
+#[test]
+fn test_key_schedule_separates_directions_and_purposes() {
+    let schedule = KeySchedule::new(SHA256, &[1u8; 64], &[3u8; 32]);
+
+    let keys = schedule.session_keys();
+    assert_ne!(keys.k_client_to_server, keys.k_server_to_client);
+    assert_ne!(keys.nonce_base_c2s, keys.nonce_base_s2c);
+
+    let finished = schedule.finished_keys();
+    assert_ne!(finished.client, finished.server);
+    assert_ne!(finished.client.as_bytes(), keys.k_client_to_server);
+
+    assert_ne!(
+        schedule.exporter_master_secret(),
+        schedule.resumption_master_secret()
+    );
+    assert_ne!(
+        schedule.exporter_master_secret(),
+        schedule.client_application_traffic_secret()
+    );
+}
+
+#[test]
+fn test_key_schedule_traffic_keys_come_from_traffic_secrets() {
+    let schedule = KeySchedule::new(SHA256, &[1u8; 64], &[3u8; 32]);
+    let keys = schedule.session_keys();
+
+    let (key, iv) = traffic_key_iv(SHA256, schedule.client_application_traffic_secret());
+    assert_eq!(*key, keys.k_client_to_server);
+    assert_eq!(*iv, keys.nonce_base_c2s);
+
+    let (key, iv) = traffic_key_iv(SHA256, schedule.server_application_traffic_secret());
+    assert_eq!(*key, keys.k_server_to_client);
+    assert_eq!(*iv, keys.nonce_base_s2c);
+}
+
+#[test]
+fn test_key_schedule_binds_secret_and_transcript() {
+    let base = KeySchedule::new(SHA256, &[1u8; 64], &[3u8; 32]).session_keys();
+    let other_secret = KeySchedule::new(SHA256, &[9u8; 64], &[3u8; 32]).session_keys();
+    let other_transcript = KeySchedule::new(SHA256, &[1u8; 64], &[9u8; 32]).session_keys();
+
+    assert_ne!(base.k_client_to_server, other_secret.k_client_to_server);
+    assert_ne!(base.k_client_to_server, other_transcript.k_client_to_server);
+}
+
+#[test]
+fn test_next_traffic_secret_ratchets() {
+    let schedule = KeySchedule::new(SHA256, &[1u8; 64], &[3u8; 32]);
+    let secret = schedule.client_application_traffic_secret().to_vec();
+    let next = next_traffic_secret(SHA256, &secret);
+    assert_ne!(secret, next.as_bytes());
+    assert_ne!(traffic_key_iv(SHA256, &secret).0, traffic_key_iv(SHA256, &next).0);
+}
+
+#[test]
+fn test_expand_label_depends_on_label_context_and_length() {
+    let secret = [7u8; 32];
+    let mut a = [0u8; 32];
+    let mut b = [0u8; 32];
+    let mut c = [0u8; 32];
+    let mut short = [0u8; 16];
+    hkdf_expand_label(SHA256, &secret, b"key", b"", &mut a);
+    hkdf_expand_label(SHA256, &secret, b"iv", b"", &mut b);
+    hkdf_expand_label(SHA256, &secret, b"key", b"ctx", &mut c);
+    hkdf_expand_label(SHA256, &secret, b"key", b"", &mut short);
+    assert_ne!(a, b);
+    assert_ne!(a, c);
+    // The output length is part of HkdfLabel, so a shorter key is not a prefix of a longer one
+    assert_ne!(a[..16], short);
+}
+
+#[test]
+fn test_sha384_key_schedule() {
+    let sha256 = KeySchedule::new(SHA256, &[1u8; 64], &[3u8; 32]);
+    let sha384 = KeySchedule::new(HashAlgorithm::Sha384, &[1u8; 64], &[3u8; 48]);
+
+    assert_eq!(sha384.client_application_traffic_secret().len(), 48);
+    assert_eq!(sha384.finished_keys().client.len(), 48);
+    assert_ne!(
+        sha256.session_keys().k_client_to_server,
+        sha384.session_keys().k_client_to_server
+    );
+}
+
+#[test]
+fn test_running_hash_snapshots_match_one_shot_digest() {
+    for hash in [SHA256, HashAlgorithm::Sha384] {
+        let mut running = hash.running();
+        assert_eq!(running.snapshot(), hash.digest(b""));
+
+        running.update(b"client hello");
+        let first = running.snapshot();
+        assert_eq!(first, hash.digest(b"client hello"));
+        // Taking a snapshot does not end the hash
+        assert_eq!(running.snapshot(), first);
+
+        running.update(b" server hello");
+        assert_eq!(running.snapshot(), hash.digest(b"client hello server hello"));
+        assert_eq!(running.algorithm(), hash);
+    }
+}
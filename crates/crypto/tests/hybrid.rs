@@ -0,0 +1,112 @@
+use hybrid_kyber_crypto::hkdf::{HashAlgorithm, Sha256, Sha384};
+use hybrid_kyber_crypto::hybrid::{Combiner, CombinerInput, ConcatHkdf, HybridKem, XWing};
+use hybrid_kyber_crypto::kyber::{Kyber768Kem, MlKem768Kem};
+use hybrid_kyber_crypto::secret::SecretBytes;
+use hybrid_kyber_crypto::traits::Kem;
+use hybrid_kyber_crypto::x25519::X25519Kem;
+use sha3::{Digest, Sha3_256};
+
+fn roundtrip<K: Kem<SharedSecret = SecretBytes>>() -> SecretBytes {
+    let (pk, sk) = K::generate_keypair();
+    let (ct, shared_secret_1) = K::encapsulate(&pk).unwrap();
+    let shared_secret_2 = K::decapsulate(&sk, &ct).unwrap();
+    assert_eq!(shared_secret_1, shared_secret_2);
+    shared_secret_1
+}
+
+#[test]
+fn test_hybrid_roundtrip_with_each_combiner() {
+    assert_eq!(roundtrip::<HybridKem<MlKem768Kem, X25519Kem, ConcatHkdf>>().len(), 32);
+    assert_eq!(roundtrip::<HybridKem<MlKem768Kem, X25519Kem, XWing>>().len(), 32);
+    assert_eq!(roundtrip::<HybridKem<Kyber768Kem, X25519Kem, ConcatHkdf>>().len(), 32);
+    assert_eq!(roundtrip::<HybridKem<MlKem768Kem, X25519Kem, ConcatHkdf<Sha384>>>().len(), 48);
+    assert_eq!(HybridKem::<MlKem768Kem, X25519Kem, ConcatHkdf<Sha384>>::SHARED_SECRET_SIZE, 48);
+}
+
+#[test]
+fn test_concat_hkdf_follows_its_hash() {
+    let input = CombinerInput {
+        ss_a: &[1u8; 32],
+        ss_b: &[2u8; 56],
+        ct_a: &[],
+        ct_b: &[],
+        pk_a: &[],
+        pk_b: &[],
+    };
+    let ikm = [[1u8; 32].as_slice(), &[2u8; 56]].concat();
+    for hash in [HashAlgorithm::Sha256, HashAlgorithm::Sha384] {
+        let prk = hash.extract(&vec![0u8; hash.output_len()], &ikm);
+        let mut expected = vec![0u8; hash.output_len()];
+        hash.expand(&prk, b"hybrid-kem concat", &mut expected);
+        let combined = match hash {
+            HashAlgorithm::Sha256 => ConcatHkdf::<Sha256>::combine(&input),
+            HashAlgorithm::Sha384 => ConcatHkdf::<Sha384>::combine(&input),
+        };
+        assert_eq!(combined.as_bytes(), expected);
+    }
+}
+
+#[test]
+fn test_hybrid_encoding_concatenates_components() {
+    type Hybrid = HybridKem<MlKem768Kem, X25519Kem, XWing>;
+    let (pk, _) = Hybrid::generate_keypair();
+    let (ct, _) = Hybrid::encapsulate(&pk).unwrap();
+
+    assert_eq!(Hybrid::public_key_to_bytes(&pk).len(), 1184 + 32);
+    assert_eq!(Hybrid::ciphertext_to_bytes(&ct).len(), 1088 + 32);
+    assert_eq!(Hybrid::PUBLIC_KEY_SIZE, 1184 + 32);
+    assert_eq!(Hybrid::CIPHERTEXT_SIZE, 1088 + 32);
+}
+
+#[test]
+fn test_hybrid_secret_key_encoding_roundtrips() {
+    type Hybrid = HybridKem<MlKem768Kem, X25519Kem, XWing>;
+    let (pk, sk) = Hybrid::generate_keypair();
+    let (ct, ss) = Hybrid::encapsulate(&pk).unwrap();
+
+    let sk_bytes = Hybrid::secret_key_to_bytes(&sk);
+    assert_eq!(sk_bytes.len(), Hybrid::SECRET_KEY_SIZE);
+    let sk = Hybrid::secret_key_from_bytes(&sk_bytes).unwrap();
+    let ct = Hybrid::ciphertext_from_bytes(&Hybrid::ciphertext_to_bytes(&ct)).unwrap();
+    assert_eq!(Hybrid::decapsulate(&sk, &ct).unwrap(), ss);
+    assert!(Hybrid::secret_key_from_bytes(&sk_bytes[1..]).is_err());
+}
+
+#[test]
+fn test_hybrid_secret_depends_on_both_components() {
+    type Hybrid = HybridKem<MlKem768Kem, X25519Kem, ConcatHkdf>;
+    let (pk, sk) = Hybrid::generate_keypair();
+    let (mut ct, ss) = Hybrid::encapsulate(&pk).unwrap();
+
+    let (other_x25519_ct, _) = X25519Kem::encapsulate(&pk.b).unwrap();
+    ct.b = other_x25519_ct;
+    assert_ne!(Hybrid::decapsulate(&sk, &ct).unwrap(), ss);
+}
+
+#[test]
+fn test_xwing_combiner_layout() {
+    let input = CombinerInput {
+        ss_a: &[1u8; 32],
+        ss_b: &[2u8; 32],
+        ct_a: &[3u8; 1088],
+        ct_b: &[4u8; 32],
+        pk_a: &[5u8; 1184],
+        pk_b: &[6u8; 32],
+    };
+
+    let mut hasher = Sha3_256::new();
+    hasher.update([1u8; 32]);
+    hasher.update([2u8; 32]);
+    hasher.update([4u8; 32]);
+    hasher.update([6u8; 32]);
+    hasher.update(b"\\.//^\\");
+    assert_eq!(XWing::combine(&input).as_bytes(), hasher.finalize().as_slice());
+
+    // The classical public key is bound; the PQ public key and ciphertext are not
+    let changed_pk_b = CombinerInput {
+        pk_b: &[7u8; 32],
+        ..input
+    };
+    assert_ne!(XWing::combine(&changed_pk_b), XWing::combine(&input));
+    assert_ne!(<ConcatHkdf>::combine(&input), XWing::combine(&input));
+}
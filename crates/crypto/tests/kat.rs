@@ -0,0 +1,630 @@
+//! Tests driven by the JSON files under `tests/vectors`. Most are known answers from published
+//! sources; `kem_self_regression.json` and the `key_schedule` entries of `hkdf.json` are this
+//! crate's own output and only catch unintended changes.
+
+use std::fs;
+use std::path::Path;
+
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::Aes256;
+use hybrid_kyber_crypto::aead::{Aes256GcmAead, ChaCha20Poly1305Aead};
+use hybrid_kyber_crypto::dhkem::DhKem;
+use hybrid_kyber_crypto::hkdf::{
+    derive_session_keys_with_info, next_traffic_secret, rekey_traffic_secret, HashAlgorithm,
+    KeySchedule,
+};
+use hybrid_kyber_crypto::hybrid::{ConcatHkdf, HybridKem, XWing};
+use hybrid_kyber_crypto::kyber::{
+    Kyber1024Kem, Kyber512Kem, Kyber768Kem, MlKem1024Kem, MlKem512Kem, MlKem768Kem,
+};
+use hybrid_kyber_crypto::p256::P256Dh;
+use hybrid_kyber_crypto::secret::SecretBytes;
+use hybrid_kyber_crypto::traits::{Aead, Dh, Kem};
+use hybrid_kyber_crypto::x25519::{X25519Dh, X25519Kem};
+use hybrid_kyber_crypto::x448::X448Dh;
+use rand::{CryptoRng, RngCore};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::Shake256;
+
+fn load<T: DeserializeOwned>(name: &str) -> T {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/vectors").join(name);
+    let json = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    serde_json::from_str(&json).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+fn unhex(s: &str) -> Vec<u8> {
+    hex::decode(s).unwrap()
+}
+
+fn hash_algorithm(name: &str) -> HashAlgorithm {
+    match name {
+        "Sha256" => HashAlgorithm::Sha256,
+        "Sha384" => HashAlgorithm::Sha384,
+        _ => panic!("unknown hash {}", name),
+    }
+}
+
+/// NIST SP 800-90A AES-256 CTR_DRBG without a derivation function, the `randombytes`
+/// behind PQCgenKAT_kem.c. Every `fill_bytes` call ends with a state update, so output
+/// depends on how callers split their draws.
+struct KatDrbg {
+    key: [u8; 32],
+    v: [u8; 16],
+}
+
+impl KatDrbg {
+    fn new(seed: &[u8]) -> Self {
+        let mut drbg = KatDrbg {
+            key: [0; 32],
+            v: [0; 16],
+        };
+        drbg.update(Some(seed.try_into().expect("48-byte seed")));
+        drbg
+    }
+
+    fn next_block(&mut self) -> [u8; 16] {
+        for byte in self.v.iter_mut().rev() {
+            *byte = byte.wrapping_add(1);
+            if *byte != 0 {
+                break;
+            }
+        }
+        let mut block = self.v.into();
+        Aes256::new(&self.key.into()).encrypt_block(&mut block);
+        block.into()
+    }
+
+    fn update(&mut self, provided: Option<&[u8; 48]>) {
+        let mut temp = [0u8; 48];
+        for chunk in temp.chunks_mut(16) {
+            chunk.copy_from_slice(&self.next_block());
+        }
+        if let Some(provided) = provided {
+            temp.iter_mut().zip(provided).for_each(|(t, p)| *t ^= p);
+        }
+        self.key.copy_from_slice(&temp[..32]);
+        self.v.copy_from_slice(&temp[32..]);
+    }
+}
+
+impl RngCore for KatDrbg {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(16) {
+            let block = self.next_block();
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        self.update(None);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for KatDrbg {}
+
+/// Hands out published randomness in order, for vectors that list it instead of a DRBG seed
+struct ReplayRng {
+    bytes: Vec<u8>,
+}
+
+impl ReplayRng {
+    fn new(parts: &[&str]) -> Self {
+        ReplayRng {
+            bytes: parts.iter().flat_map(|part| unhex(part)).collect(),
+        }
+    }
+}
+
+impl RngCore for ReplayRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        assert!(dest.len() <= self.bytes.len(), "draw exceeds the vector's randomness");
+        dest.copy_from_slice(&self.bytes[..dest.len()]);
+        self.bytes.drain(..dest.len());
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for ReplayRng {}
+
+#[derive(Deserialize)]
+struct KemFile {
+    vectors: Vec<KemVector>,
+    #[serde(default)]
+    xwing: Vec<XWingVector>,
+}
+
+#[derive(Deserialize)]
+struct KemVector {
+    kem: String,
+    source: String,
+    seed: String,
+    pk: String,
+    sk: String,
+    ct: String,
+    ss: String,
+    /// SHA-256 of the `.rsp` file PQClean's nistkat test prints for one iteration
+    nistkat_sha256: Option<String>,
+}
+
+fn check_kem<K: Kem<SharedSecret = SecretBytes>>(v: &KemVector) {
+    let mut rng = KatDrbg::new(&unhex(&v.seed));
+    let (pk, sk) = K::generate_keypair_with_rng(&mut rng);
+    assert_eq!(hex::encode(K::public_key_to_bytes(&pk)), v.pk, "{} pk", v.kem);
+    assert_eq!(hex::encode(K::secret_key_to_bytes(&sk)), v.sk, "{} sk", v.kem);
+
+    let (ct, ss) = K::encapsulate_with_rng(&pk, &mut rng).unwrap();
+    assert_eq!(hex::encode(K::ciphertext_to_bytes(&ct)), v.ct, "{} ct", v.kem);
+    assert_eq!(hex::encode(&ss), v.ss, "{} ss", v.kem);
+
+    // Decapsulation from the encoded forms alone
+    let sk = K::secret_key_from_bytes(&unhex(&v.sk)).unwrap();
+    let ct = K::ciphertext_from_bytes(&unhex(&v.ct)).unwrap();
+    assert_eq!(hex::encode(K::decapsulate(&sk, &ct).unwrap()), v.ss, "{} decapsulate", v.kem);
+    assert!(K::public_key_from_bytes(&unhex(&v.pk)).is_ok());
+}
+
+#[derive(Deserialize)]
+struct XWingVector {
+    source: String,
+    seed: String,
+    eseed: String,
+    pk: String,
+    ct: String,
+    ss: String,
+}
+
+/// X-Wing as this crate composes it: ML-KEM-768 and raw X25519 under the X-Wing combiner
+type XWingKem = HybridKem<MlKem768Kem, DhKem<X25519Dh>, XWing>;
+
+fn check_xwing(v: &XWingVector) {
+    // The draft stores the 32-byte seed as the secret key and expands it on use
+    let mut expanded = [0u8; 96];
+    Shake256::default().chain(unhex(&v.seed)).finalize_xof().read(&mut expanded);
+    let mut rng = ReplayRng::new(&[&hex::encode(expanded)]);
+    let (pk, sk) = XWingKem::generate_keypair_with_rng(&mut rng);
+    assert_eq!(hex::encode(XWingKem::public_key_to_bytes(&pk)), v.pk, "{} pk", v.source);
+
+    let (ct, ss) = XWingKem::encapsulate_with_rng(&pk, &mut ReplayRng::new(&[&v.eseed])).unwrap();
+    assert_eq!(hex::encode(XWingKem::ciphertext_to_bytes(&ct)), v.ct, "{} ct", v.source);
+    assert_eq!(hex::encode(&ss), v.ss, "{} ss", v.source);
+
+    let ct = XWingKem::ciphertext_from_bytes(&unhex(&v.ct)).unwrap();
+    assert_eq!(hex::encode(XWingKem::decapsulate(&sk, &ct).unwrap()), v.ss, "{} decapsulate", v.source);
+}
+
+/// The single-iteration response file of PQClean's nistkat test
+fn nistkat_rsp(v: &KemVector) -> String {
+    format!(
+        "count = 0\nseed = {}\npk = {}\nsk = {}\nct = {}\nss = {}\n",
+        v.seed.to_uppercase(),
+        v.pk.to_uppercase(),
+        v.sk.to_uppercase(),
+        v.ct.to_uppercase(),
+        v.ss.to_uppercase()
+    )
+}
+
+#[test]
+fn test_kem_vectors() {
+    let file: KemFile = load("kem.json");
+    for v in &file.vectors {
+        match v.kem.as_str() {
+            "Kyber512" => check_kem::<Kyber512Kem>(v),
+            "Kyber768" => check_kem::<Kyber768Kem>(v),
+            "Kyber1024" => check_kem::<Kyber1024Kem>(v),
+            "MlKem512" => check_kem::<MlKem512Kem>(v),
+            "MlKem768" => check_kem::<MlKem768Kem>(v),
+            "MlKem1024" => check_kem::<MlKem1024Kem>(v),
+            other => panic!("unknown KEM {} ({})", other, v.source),
+        }
+        if let Some(digest) = &v.nistkat_sha256 {
+            assert_eq!(
+                &hex::encode(Sha256::digest(nistkat_rsp(v))),
+                digest,
+                "{} nistkat digest",
+                v.kem
+            );
+        }
+    }
+    assert!(!file.xwing.is_empty());
+    file.xwing.iter().for_each(check_xwing);
+}
+
+/// Not known answers: pins this crate's own output for KEMs without published vectors
+#[test]
+fn test_kem_self_regression_vectors() {
+    let file: KemFile = load("kem_self_regression.json");
+    for v in &file.vectors {
+        match v.kem.as_str() {
+            "X25519Kem" => check_kem::<X25519Kem>(v),
+            "DhKem<X25519>" => check_kem::<DhKem<X25519Dh>>(v),
+            "DhKem<P256>" => check_kem::<DhKem<P256Dh>>(v),
+            "DhKem<X448>" => check_kem::<DhKem<X448Dh>>(v),
+            "HybridKem<MlKem768, X25519Kem, ConcatHkdf>" => {
+                check_kem::<HybridKem<MlKem768Kem, X25519Kem, ConcatHkdf>>(v)
+            }
+            "HybridKem<MlKem768, X25519Kem, XWing>" => {
+                check_kem::<HybridKem<MlKem768Kem, X25519Kem, XWing>>(v)
+            }
+            "HybridKem<MlKem1024, DhKem<X448>, ConcatHkdf>" => {
+                check_kem::<HybridKem<MlKem1024Kem, DhKem<X448Dh>, ConcatHkdf>>(v)
+            }
+            other => panic!("unknown KEM {} ({})", other, v.source),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AcvpFile {
+    key_gen: Vec<AcvpKeyGen>,
+    encapsulation: Vec<AcvpEncapsulation>,
+    decapsulation: Vec<AcvpDecapsulationGroup>,
+}
+
+#[derive(Deserialize)]
+struct AcvpKeyGen {
+    source: String,
+    kem: String,
+    d: String,
+    z: String,
+    ek: String,
+    dk: String,
+}
+
+#[derive(Deserialize)]
+struct AcvpEncapsulation {
+    source: String,
+    kem: String,
+    ek: String,
+    m: String,
+    c: String,
+    k: String,
+}
+
+#[derive(Deserialize)]
+struct AcvpDecapsulationGroup {
+    source: String,
+    kem: String,
+    dk: String,
+    tests: Vec<AcvpDecapsulation>,
+}
+
+#[derive(Deserialize)]
+struct AcvpDecapsulation {
+    tc_id: u32,
+    reason: String,
+    c: String,
+    k: String,
+}
+
+fn check_acvp_key_gen<K: Kem>(v: &AcvpKeyGen) {
+    let (pk, sk) = K::generate_keypair_with_rng(&mut ReplayRng::new(&[&v.d, &v.z]));
+    assert_eq!(hex::encode(K::public_key_to_bytes(&pk)), v.ek, "{} ek", v.source);
+    assert_eq!(hex::encode(K::secret_key_to_bytes(&sk)), v.dk, "{} dk", v.source);
+}
+
+fn check_acvp_encapsulation<K: Kem<SharedSecret = SecretBytes>>(v: &AcvpEncapsulation) {
+    let pk = K::public_key_from_bytes(&unhex(&v.ek)).expect(&v.source);
+    let (ct, ss) = K::encapsulate_with_rng(&pk, &mut ReplayRng::new(&[&v.m])).unwrap();
+    assert_eq!(hex::encode(K::ciphertext_to_bytes(&ct)), v.c, "{} c", v.source);
+    assert_eq!(hex::encode(&ss), v.k, "{} k", v.source);
+}
+
+fn check_acvp_decapsulation<K: Kem<SharedSecret = SecretBytes>>(g: &AcvpDecapsulationGroup) {
+    let sk = K::secret_key_from_bytes(&unhex(&g.dk)).expect(&g.source);
+    for t in &g.tests {
+        // Modified ciphertexts must yield the implicit-rejection key, not an error
+        let ct = K::ciphertext_from_bytes(&unhex(&t.c)).unwrap();
+        let ss = K::decapsulate(&sk, &ct).unwrap();
+        assert_eq!(hex::encode(&ss), t.k, "{} tcId {} ({})", g.source, t.tc_id, t.reason);
+    }
+}
+
+#[test]
+fn test_mlkem_acvp_vectors() {
+    let file: AcvpFile = load("mlkem_acvp.json");
+    for v in &file.key_gen {
+        match v.kem.as_str() {
+            "MlKem512" => check_acvp_key_gen::<MlKem512Kem>(v),
+            "MlKem768" => check_acvp_key_gen::<MlKem768Kem>(v),
+            "MlKem1024" => check_acvp_key_gen::<MlKem1024Kem>(v),
+            other => panic!("unknown KEM {}", other),
+        }
+    }
+    for v in &file.encapsulation {
+        match v.kem.as_str() {
+            "MlKem512" => check_acvp_encapsulation::<MlKem512Kem>(v),
+            "MlKem768" => check_acvp_encapsulation::<MlKem768Kem>(v),
+            "MlKem1024" => check_acvp_encapsulation::<MlKem1024Kem>(v),
+            other => panic!("unknown KEM {}", other),
+        }
+    }
+    for g in &file.decapsulation {
+        match g.kem.as_str() {
+            "MlKem512" => check_acvp_decapsulation::<MlKem512Kem>(g),
+            "MlKem768" => check_acvp_decapsulation::<MlKem768Kem>(g),
+            "MlKem1024" => check_acvp_decapsulation::<MlKem1024Kem>(g),
+            other => panic!("unknown KEM {}", other),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct DhFile {
+    vectors: Vec<DhVector>,
+    iterated: Vec<DhIteratedVector>,
+}
+
+#[derive(Deserialize)]
+struct DhIteratedVector {
+    source: String,
+    group: String,
+    iterations: u32,
+    start: String,
+    result: String,
+}
+
+#[derive(Deserialize)]
+struct DhVector {
+    source: String,
+    group: String,
+    sk: String,
+    pk: Option<String>,
+    peer_pk: String,
+    shared: String,
+}
+
+fn check_dh<D: Dh>(v: &DhVector)
+where
+    D::SharedSecret: AsRef<[u8]>,
+{
+    let sk = D::secret_key_from_bytes(&unhex(&v.sk)).expect(&v.source);
+    if let Some(pk) = &v.pk {
+        assert_eq!(&hex::encode(D::public_key_to_bytes(&D::public_key(&sk))), pk, "{}", v.source);
+    }
+    let peer = D::public_key_from_bytes(&unhex(&v.peer_pk)).expect(&v.source);
+    assert_eq!(hex::encode(D::diffie_hellman(&sk, &peer)), v.shared, "{}", v.source);
+}
+
+/// RFC 7748 §5.2: each round's output is the next scalar and the previous scalar the next u
+fn check_dh_iterated<D: Dh>(v: &DhIteratedVector)
+where
+    D::SharedSecret: AsRef<[u8]>,
+{
+    let mut k = unhex(&v.start);
+    let mut u = k.clone();
+    for _ in 0..v.iterations {
+        let sk = D::secret_key_from_bytes(&k).expect(&v.source);
+        let peer = D::public_key_from_bytes(&u).expect(&v.source);
+        let next = D::diffie_hellman(&sk, &peer).as_ref().to_vec();
+        u = std::mem::replace(&mut k, next);
+    }
+    assert_eq!(hex::encode(k), v.result, "{}", v.source);
+}
+
+#[test]
+fn test_dh_vectors() {
+    let file: DhFile = load("dh.json");
+    for v in &file.vectors {
+        match v.group.as_str() {
+            "X25519" => check_dh::<X25519Dh>(v),
+            "X448" => check_dh::<X448Dh>(v),
+            "P256" => check_dh::<P256Dh>(v),
+            other => panic!("unknown group {}", other),
+        }
+    }
+    for v in &file.iterated {
+        match v.group.as_str() {
+            "X25519" => check_dh_iterated::<X25519Dh>(v),
+            "X448" => check_dh_iterated::<X448Dh>(v),
+            other => panic!("unknown group {}", other),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AeadFile {
+    vectors: Vec<AeadVector>,
+}
+
+#[derive(Deserialize)]
+struct AeadVector {
+    source: String,
+    aead: String,
+    key: String,
+    nonce_base: String,
+    seq: u64,
+    aad: String,
+    plaintext: String,
+    ciphertext: String,
+}
+
+fn check_aead<A: Aead>(v: &AeadVector) {
+    let key: [u8; 32] = unhex(&v.key).try_into().unwrap();
+    let nonce_base: [u8; 12] = unhex(&v.nonce_base).try_into().unwrap();
+    let aad = unhex(&v.aad);
+    let sealed = A::encrypt(&key, &nonce_base, v.seq, &aad, &unhex(&v.plaintext));
+    assert_eq!(hex::encode(&sealed), v.ciphertext, "{}", v.source);
+    let opened = A::decrypt(&key, &nonce_base, v.seq, &aad, &sealed).unwrap();
+    assert_eq!(hex::encode(opened), v.plaintext, "{}", v.source);
+
+    assert!(A::decrypt(&key, &nonce_base, v.seq + 1, &aad, &sealed).is_err());
+    let mut tampered = sealed;
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(A::decrypt(&key, &nonce_base, v.seq, &aad, &tampered).is_err());
+}
+
+#[test]
+fn test_aead_vectors() {
+    let file: AeadFile = load("aead.json");
+    for v in &file.vectors {
+        match v.aead.as_str() {
+            "ChaCha20Poly1305" => check_aead::<ChaCha20Poly1305Aead>(v),
+            "Aes256Gcm" => check_aead::<Aes256GcmAead>(v),
+            other => panic!("unknown AEAD {}", other),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct HkdfFile {
+    hkdf: Vec<HkdfVector>,
+    hmac: Vec<HmacVector>,
+    key_schedule: Vec<KeyScheduleVector>,
+}
+
+#[derive(Deserialize)]
+struct HkdfVector {
+    source: String,
+    hash: String,
+    ikm: String,
+    salt: String,
+    info: String,
+    prk: String,
+    okm: String,
+}
+
+#[derive(Deserialize)]
+struct HmacVector {
+    source: String,
+    hash: String,
+    key: String,
+    data: String,
+    tag: String,
+}
+
+#[derive(Deserialize)]
+struct KeyScheduleVector {
+    hash: String,
+    shared_secret: String,
+    transcript: String,
+    client_application_traffic_secret: String,
+    server_application_traffic_secret: String,
+    exporter_master_secret: String,
+    resumption_master_secret: String,
+    client_finished_key: String,
+    server_finished_key: String,
+    k_client_to_server: String,
+    k_server_to_client: String,
+    nonce_base_c2s: String,
+    nonce_base_s2c: String,
+    exporter_label: String,
+    exporter_context: String,
+    exported: String,
+    next_client_traffic_secret: String,
+    rekey_shared_secret: String,
+    rekey_transcript: String,
+    rekeyed_client_traffic_secret: String,
+}
+
+#[test]
+fn test_hkdf_vectors() {
+    let file: HkdfFile = load("hkdf.json");
+    for v in &file.hkdf {
+        let hash = hash_algorithm(&v.hash);
+        let ikm = unhex(&v.ikm);
+        let prk = hash.extract(&unhex(&v.salt), &ikm);
+        assert_eq!(hex::encode(&prk), v.prk, "{}", v.source);
+        let mut okm = vec![0u8; v.okm.len() / 2];
+        hash.expand(&prk, &unhex(&v.info), &mut okm);
+        assert_eq!(hex::encode(&okm), v.okm, "{}", v.source);
+
+        // The v1 derivation is the same HKDF with the transcript as salt, over ss_pq ‖ ss_classical
+        let keys = derive_session_keys_with_info(
+            hash,
+            &ikm[..10],
+            &ikm[10..],
+            &unhex(&v.salt),
+            &unhex(&v.info),
+        );
+        let v1_okm = [&keys.k_client_to_server[..], &keys.k_server_to_client[..]].concat();
+        assert_eq!(hex::encode(&v1_okm[..okm.len()]), v.okm, "{} (v1)", v.source);
+    }
+
+    for v in &file.hmac {
+        let hash = hash_algorithm(&v.hash);
+        let tag = hash.hmac(&unhex(&v.key), &unhex(&v.data));
+        assert_eq!(hex::encode(&tag), v.tag, "{}", v.source);
+        assert!(hash.verify_hmac(&unhex(&v.key), &unhex(&v.data), &tag));
+    }
+}
+
+/// Not known answers: pins this crate's own key schedule output, which no standard publishes
+#[test]
+fn test_key_schedule_self_regression_vectors() {
+    let file: HkdfFile = load("hkdf.json");
+    for v in &file.key_schedule {
+        let hash = hash_algorithm(&v.hash);
+        let schedule = KeySchedule::new(hash, &unhex(&v.shared_secret), &unhex(&v.transcript));
+        let client_traffic = schedule.client_application_traffic_secret();
+        assert_eq!(hex::encode(client_traffic), v.client_application_traffic_secret);
+        assert_eq!(
+            hex::encode(schedule.server_application_traffic_secret()),
+            v.server_application_traffic_secret
+        );
+        assert_eq!(hex::encode(schedule.exporter_master_secret()), v.exporter_master_secret);
+        assert_eq!(hex::encode(schedule.resumption_master_secret()), v.resumption_master_secret);
+
+        let finished_keys = schedule.finished_keys();
+        assert_eq!(hex::encode(&finished_keys.client), v.client_finished_key);
+        assert_eq!(hex::encode(&finished_keys.server), v.server_finished_key);
+
+        let keys = schedule.session_keys();
+        assert_eq!(hex::encode(keys.k_client_to_server), v.k_client_to_server);
+        assert_eq!(hex::encode(keys.k_server_to_client), v.k_server_to_client);
+        assert_eq!(hex::encode(keys.nonce_base_c2s), v.nonce_base_c2s);
+        assert_eq!(hex::encode(keys.nonce_base_s2c), v.nonce_base_s2c);
+
+        let exported = schedule.export_keying_material(
+            &unhex(&v.exporter_label),
+            &unhex(&v.exporter_context),
+            v.exported.len() / 2,
+        );
+        assert_eq!(hex::encode(exported.unwrap()), v.exported);
+
+        assert_eq!(
+            hex::encode(next_traffic_secret(hash, client_traffic)),
+            v.next_client_traffic_secret
+        );
+        let rekeyed = rekey_traffic_secret(
+            hash,
+            client_traffic,
+            &unhex(&v.rekey_shared_secret),
+            &unhex(&v.rekey_transcript),
+        );
+        assert_eq!(hex::encode(rekeyed), v.rekeyed_client_traffic_secret);
+    }
+}
@@ -1,10 +1,145 @@
-use hybrid_kyber_crypto::kyber::Kyber768Kem;
-use hybrid_kyber_crypto::traits::Kem;
+use hybrid_kyber_crypto::kyber::{
+    Kyber1024Kem, Kyber512Kem, Kyber768Kem, MlKem1024Kem, MlKem512Kem, MlKem768Kem,
+};
+use hybrid_kyber_crypto::secret::SecretBytes;
+use hybrid_kyber_crypto::traits::{Kem, KemError};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn roundtrip<K: Kem<SharedSecret = SecretBytes>>() {
+    let (pk, sk) = K::generate_keypair();
+    let (ct, shared_secret_1) = K::encapsulate(&pk).unwrap();
+    let shared_secret_2 = K::decapsulate(&sk, &ct).unwrap();
+    assert_eq!(shared_secret_1, shared_secret_2);
+    assert_eq!(shared_secret_1.len(), K::SHARED_SECRET_SIZE);
+
+    // Everything survives a trip through its wire encoding
+    let pk_bytes = K::public_key_to_bytes(&pk);
+    let sk_bytes = K::secret_key_to_bytes(&sk);
+    let ct_bytes = K::ciphertext_to_bytes(&ct);
+    assert_eq!(pk_bytes.len(), K::PUBLIC_KEY_SIZE);
+    assert_eq!(sk_bytes.len(), K::SECRET_KEY_SIZE);
+    assert_eq!(ct_bytes.len(), K::CIPHERTEXT_SIZE);
+
+    let sk = K::secret_key_from_bytes(&sk_bytes).unwrap();
+    let ct = K::ciphertext_from_bytes(&ct_bytes).unwrap();
+    assert_eq!(K::decapsulate(&sk, &ct).unwrap(), shared_secret_1);
+    assert_eq!(K::public_key_to_bytes(&K::public_key_from_bytes(&pk_bytes).unwrap()), pk_bytes);
+}
 
 #[test]
 fn test_encapsulate_decapsulate() {
+    roundtrip::<Kyber768Kem>();
+}
+
+#[test]
+fn test_kyber512_and_1024_encapsulate_decapsulate() {
+    roundtrip::<Kyber512Kem>();
+    roundtrip::<Kyber1024Kem>();
+}
+
+#[test]
+fn test_mlkem768_encapsulate_decapsulate() {
+    roundtrip::<MlKem768Kem>();
+}
+
+#[test]
+fn test_mlkem512_and_1024_encapsulate_decapsulate() {
+    roundtrip::<MlKem512Kem>();
+    roundtrip::<MlKem1024Kem>();
+}
+
+#[test]
+fn test_mlkem768_wrong_key_rejects_implicitly() {
+    let (pk, _) = MlKem768Kem::generate_keypair();
+    let (_, other_sk) = MlKem768Kem::generate_keypair();
+    let (ct, shared_secret_1) = MlKem768Kem::encapsulate(&pk).unwrap();
+    let shared_secret_2 = MlKem768Kem::decapsulate(&other_sk, &ct).unwrap();
+    assert_ne!(shared_secret_1, shared_secret_2);
+    assert_eq!(shared_secret_2.len(), 32);
+}
+
+#[test]
+fn test_wrong_length_encodings_rejected() {
+    assert!(matches!(
+        MlKem768Kem::public_key_from_bytes(&[0u8; 1183]),
+        Err(KemError::InvalidPublicKey)
+    ));
+    assert!(matches!(
+        MlKem768Kem::secret_key_from_bytes(&[0u8; 32]),
+        Err(KemError::InvalidSecretKey)
+    ));
+    assert!(matches!(
+        Kyber768Kem::ciphertext_from_bytes(&[0u8; 1089]),
+        Err(KemError::InvalidCiphertext)
+    ));
+}
+
+fn modulus_check<K: Kem<SharedSecret = SecretBytes>>() {
+    let (pk, _) = K::generate_keypair();
+    let mut bytes = K::public_key_to_bytes(&pk);
+    // First coefficient of t_hat to 0xfff, at or above q = 3329
+    bytes[0] = 0xff;
+    bytes[1] |= 0x0f;
+    // ML-KEM rejects the key as it is decoded, PQClean Kyber when encapsulating to it
+    let result = K::public_key_from_bytes(&bytes).and_then(|pk| K::encapsulate(&pk).map(drop));
+    assert!(matches!(result, Err(KemError::ModulusCheckFailed)));
+}
+
+#[test]
+fn test_unreduced_public_keys_rejected() {
+    modulus_check::<MlKem512Kem>();
+    modulus_check::<MlKem768Kem>();
+    modulus_check::<MlKem1024Kem>();
+    modulus_check::<Kyber768Kem>();
+}
+
+#[test]
+fn test_kyber_matches_pqcrypto() {
+    use pqcrypto_kyber::kyber768;
+    use pqcrypto_traits::kem::{Ciphertext, PublicKey, SecretKey, SharedSecret};
+
+    // Our keys, pqcrypto's encapsulation
     let (pk, sk) = Kyber768Kem::generate_keypair();
-    let (ct, shared_secret_1) = Kyber768Kem::encapsulate(&pk);
-    let shared_secret_2 = Kyber768Kem::decapsulate(&sk, &ct);
-    assert_eq!(shared_secret_1, shared_secret_2);
+    let pq_pk = kyber768::PublicKey::from_bytes(&Kyber768Kem::public_key_to_bytes(&pk)).unwrap();
+    let (pq_ss, pq_ct) = kyber768::encapsulate(&pq_pk);
+    let ct = Kyber768Kem::ciphertext_from_bytes(pq_ct.as_bytes()).unwrap();
+    assert_eq!(Kyber768Kem::decapsulate(&sk, &ct).unwrap().as_bytes(), pq_ss.as_bytes());
+
+    // pqcrypto's keys, our encapsulation, including implicit rejection of a tampered ciphertext
+    let (pq_pk, pq_sk) = kyber768::keypair();
+    let pk = Kyber768Kem::public_key_from_bytes(pq_pk.as_bytes()).unwrap();
+    let sk = Kyber768Kem::secret_key_from_bytes(pq_sk.as_bytes()).unwrap();
+    let (ct, ss) = Kyber768Kem::encapsulate(&pk).unwrap();
+    let mut ct_bytes = Kyber768Kem::ciphertext_to_bytes(&ct);
+    let pq_ct = kyber768::Ciphertext::from_bytes(&ct_bytes).unwrap();
+    assert_eq!(kyber768::decapsulate(&pq_ct, &pq_sk).as_bytes(), &ss[..]);
+
+    ct_bytes[0] ^= 1;
+    let pq_ct = kyber768::Ciphertext::from_bytes(&ct_bytes).unwrap();
+    let ct = Kyber768Kem::ciphertext_from_bytes(&ct_bytes).unwrap();
+    assert_eq!(
+        kyber768::decapsulate(&pq_ct, &pq_sk).as_bytes(),
+        &Kyber768Kem::decapsulate(&sk, &ct).unwrap()[..]
+    );
+}
+
+#[test]
+fn test_seeded_rng_is_deterministic() {
+    fn run<K: Kem<SharedSecret = SecretBytes>>() -> (Vec<u8>, Vec<u8>, SecretBytes) {
+        let mut rng = StdRng::seed_from_u64(7);
+        let (pk, _) = K::generate_keypair_with_rng(&mut rng);
+        let (ct, ss) = K::encapsulate_with_rng(&pk, &mut rng).unwrap();
+        (K::public_key_to_bytes(&pk), K::ciphertext_to_bytes(&ct), ss)
+    }
+    assert_eq!(run::<MlKem768Kem>(), run::<MlKem768Kem>());
+    assert_eq!(run::<MlKem1024Kem>(), run::<MlKem1024Kem>());
+    assert_eq!(run::<Kyber512Kem>(), run::<Kyber512Kem>());
+    assert_eq!(run::<Kyber768Kem>(), run::<Kyber768Kem>());
+    assert_eq!(run::<Kyber1024Kem>(), run::<Kyber1024Kem>());
+
+    // Seeded keys interoperate with PQClean's own randomized encapsulation
+    let (pk, sk) = Kyber768Kem::generate_keypair_with_rng(&mut StdRng::seed_from_u64(7));
+    let (ct, ss) = Kyber768Kem::encapsulate(&pk).unwrap();
+    assert_eq!(Kyber768Kem::decapsulate(&sk, &ct).unwrap(), ss);
 }
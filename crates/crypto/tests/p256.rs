@@ -0,0 +1,54 @@
+use hybrid_kyber_crypto::p256::P256Dh;
+use hybrid_kyber_crypto::traits::Dh;
+
+fn unhex(s: &str) -> Vec<u8> {
+    let s: String = s.split_whitespace().collect();
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// RFC 5903 §8.1
+#[test]
+fn test_p256_ecdh_vector() {
+    let i = P256Dh::secret_key_from_bytes(&unhex(
+        "C88F01F5 10D9AC3F 70A292DA A2316DE5 44E9AAB8 AFE84049 C62A9C57 862D1433",
+    ))
+    .unwrap();
+    let gi = unhex(
+        "04 DAD0B653 94221CF9 B051E1FE CA5787D0 98DFE637 FC90B9EF 945D0C37 72581180
+            5271A046 1CDB8252 D61F1C45 6FA3E59A B1F45B33 ACCF5F58 389E0577 B8990BB3",
+    );
+    let r = P256Dh::secret_key_from_bytes(&unhex(
+        "C6EF9C5D 78AE012A 011164AC B397CE20 88685D8F 06BF9BE0 B283AB46 476BEE53",
+    ))
+    .unwrap();
+    let gr = unhex(
+        "04 D12DFB52 89C8D4F8 1208B702 70398C34 2296970A 0BCCB74C 736FC755 4494BF63
+            56FBF3CA 366CC23E 8157854C 13C58D6A AC23F046 ADA30F83 53E74F33 039872AB",
+    );
+    let gir = unhex("D6840F6B 42F6EDAF D13116E0 E1256520 2FEF8E9E CE7DCE03 812464D0 4B9442DE");
+
+    assert_eq!(P256Dh::public_key_to_bytes(&P256Dh::public_key(&i)), gi);
+    assert_eq!(P256Dh::public_key_to_bytes(&P256Dh::public_key(&r)), gr);
+    let gr = P256Dh::public_key_from_bytes(&gr).unwrap();
+    let gi = P256Dh::public_key_from_bytes(&gi).unwrap();
+    assert_eq!(P256Dh::diffie_hellman(&i, &gr).to_vec(), gir);
+    assert_eq!(P256Dh::diffie_hellman(&r, &gi).to_vec(), gir);
+}
+
+#[test]
+fn test_p256_rejects_malformed_points() {
+    let (pk, _) = P256Dh::generate_keypair();
+    let encoded = P256Dh::public_key_to_bytes(&pk);
+    assert_eq!(encoded.len(), P256Dh::PUBLIC_KEY_SIZE);
+
+    // Only the 65-byte uncompressed encoding is accepted
+    assert!(P256Dh::public_key_from_bytes(&encoded[..33]).is_none());
+
+    let mut off_curve = encoded.clone();
+    off_curve[64] ^= 0x01;
+    assert!(P256Dh::public_key_from_bytes(&off_curve).is_none());
+    assert!(P256Dh::secret_key_from_bytes(&[0u8; 32]).is_none());
+}
@@ -0,0 +1,67 @@
+use hybrid_kyber_crypto::hkdf::{derive_session_keys, HashAlgorithm, KeySchedule};
+use hybrid_kyber_crypto::kyber::MlKem768Kem;
+use hybrid_kyber_crypto::secret::SecretBytes;
+use hybrid_kyber_crypto::traits::Kem;
+use hybrid_kyber_crypto::x25519::X25519Kem;
+use zeroize::Zeroize;
+
+#[test]
+fn test_secret_bytes_zeroize_wipes_buffer() {
+    let mut secret = SecretBytes::from(vec![0xAAu8; 32]);
+    secret.zeroize();
+    // `Vec` zeroizes its whole capacity and then truncates
+    assert!(secret.is_empty());
+}
+
+#[test]
+fn test_secret_bytes_debug_is_redacted() {
+    let secret = SecretBytes::from(vec![0xABu8; 32]);
+    let printed = format!("{:?}", secret);
+    assert_eq!(printed, "SecretBytes([REDACTED; 32])");
+    assert!(!printed.to_lowercase().contains("ab"));
+}
+
+#[test]
+fn test_secret_bytes_compare_by_value() {
+    assert_eq!(SecretBytes::from(&[1u8, 2, 3][..]), SecretBytes::from(vec![1, 2, 3]));
+    assert_ne!(SecretBytes::from(&[1u8, 2, 3][..]), SecretBytes::from(vec![1, 2, 4]));
+}
+
+#[test]
+fn test_session_keys_zeroize_wipes_all_keys() {
+    let mut keys = derive_session_keys(&[1u8; 32], &[2u8; 32], &[3u8; 32]);
+    assert_ne!(keys.k_client_to_server, [0u8; 32]);
+
+    keys.zeroize();
+    assert_eq!(keys.k_client_to_server, [0u8; 32]);
+    assert_eq!(keys.k_server_to_client, [0u8; 32]);
+    assert_eq!(keys.nonce_base_c2s, [0u8; 12]);
+    assert_eq!(keys.nonce_base_s2c, [0u8; 12]);
+}
+
+#[test]
+fn test_session_keys_and_key_schedule_debug_are_redacted() {
+    let keys = derive_session_keys(&[1u8; 32], &[2u8; 32], &[3u8; 32]);
+    assert_eq!(format!("{:?}", keys), "SessionKeys { .. }");
+
+    let schedule = KeySchedule::new(HashAlgorithm::Sha256, &[1u8; 32], &[3u8; 32]);
+    assert_eq!(format!("{:?}", schedule), "KeySchedule { hash: Sha256, .. }");
+    let finished = format!("{:?}", schedule.finished_keys());
+    assert!(finished.contains("REDACTED"));
+}
+
+#[test]
+fn test_kem_shared_secrets_and_secret_keys_are_redacted() {
+    let (pk, sk) = X25519Kem::generate_keypair();
+    let (_, ss) = X25519Kem::encapsulate(&pk).unwrap();
+    assert_eq!(format!("{:?}", ss), "SecretBytes([REDACTED; 32])");
+    assert_eq!(
+        format!("{:?}", X25519Kem::secret_key_to_bytes(&sk)),
+        "SecretBytes([REDACTED; 32])"
+    );
+
+    let (pk, sk) = MlKem768Kem::generate_keypair();
+    let (ct, ss) = MlKem768Kem::encapsulate(&pk).unwrap();
+    assert_eq!(ss, MlKem768Kem::decapsulate(&sk, &ct).unwrap());
+    assert_eq!(format!("{:?}", sk), "SecretBytes([REDACTED; 2400])");
+}
@@ -1,10 +1,66 @@
-use hybrid_kyber_crypto::traits::Kem;
-use hybrid_kyber_crypto::x25519::X25519Kem;
+use hybrid_kyber_crypto::dhkem::DhKem;
+use hybrid_kyber_crypto::traits::{Dh, Kem, KemError};
+use hybrid_kyber_crypto::x25519::{X25519Dh, X25519Kem};
 
 #[test]
 fn test_x25519_kem() {
     let (pk, sk) = X25519Kem::generate_keypair();
-    let (ct, ss1) = X25519Kem::encapsulate(&pk);
-    let ss2 = X25519Kem::decapsulate(&sk, &ct);
+    let (ct, ss1) = X25519Kem::encapsulate(&pk).unwrap();
+    let ss2 = X25519Kem::decapsulate(&sk, &ct).unwrap();
     assert_eq!(ss1, ss2);
 }
+
+#[test]
+fn test_x25519_dh() {
+    let (pk_a, sk_a) = X25519Dh::generate_keypair();
+    let (pk_b, sk_b) = X25519Dh::generate_keypair();
+    assert_eq!(X25519Dh::public_key(&sk_a), pk_a);
+    assert_eq!(
+        X25519Dh::diffie_hellman(&sk_a, &pk_b),
+        X25519Dh::diffie_hellman(&sk_b, &pk_a)
+    );
+
+    let encoded = X25519Dh::public_key_to_bytes(&pk_a);
+    assert_eq!(X25519Dh::public_key_from_bytes(&encoded), Some(pk_a));
+    assert!(X25519Dh::public_key_from_bytes(&encoded[..31]).is_none());
+}
+
+#[test]
+fn test_dh_kem() {
+    let (pk, sk) = DhKem::<X25519Dh>::generate_keypair();
+    let (ct, ss1) = DhKem::<X25519Dh>::encapsulate(&pk).unwrap();
+    let ss2 = DhKem::<X25519Dh>::decapsulate(&sk, &ct).unwrap();
+    assert_eq!(ss1, ss2);
+    assert_eq!(ss1.len(), 32);
+}
+
+/// Curve25519 points of order 1, 2, 4 and 8; any scalar maps them to zero
+const LOW_ORDER_POINTS: [[u8; 32]; 4] = [
+    [0; 32],
+    [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    [
+        0xe0, 0xeb, 0x7a, 0x7c, 0x3b, 0x41, 0xb8, 0xae, 0x16, 0x56, 0xe3, 0xfa, 0xf1, 0x9f, 0xc4, 0x6a,
+        0xda, 0x09, 0x8d, 0xeb, 0x9c, 0x32, 0xb1, 0xfd, 0x86, 0x62, 0x05, 0x16, 0x5f, 0x49, 0xb8, 0x00,
+    ],
+    [
+        0x5f, 0x9c, 0x95, 0xbc, 0xa3, 0x50, 0x8c, 0x24, 0xb1, 0xd0, 0xb1, 0x55, 0x9c, 0x83, 0xef, 0x5b,
+        0x04, 0x44, 0x5c, 0xc4, 0x58, 0x1c, 0x8e, 0x86, 0xd8, 0x22, 0x4e, 0xdd, 0xd0, 0x9f, 0x11, 0x57,
+    ],
+];
+
+#[test]
+fn test_low_order_points_rejected() {
+    let (_, sk) = X25519Kem::generate_keypair();
+    for point in LOW_ORDER_POINTS {
+        let pk = X25519Kem::public_key_from_bytes(&point).unwrap();
+        assert_eq!(X25519Kem::encapsulate(&pk).unwrap_err(), KemError::NonContributory);
+        assert_eq!(X25519Kem::decapsulate(&sk, &point).unwrap_err(), KemError::NonContributory);
+
+        let pk = DhKem::<X25519Dh>::public_key_from_bytes(&point).unwrap();
+        assert_eq!(DhKem::<X25519Dh>::encapsulate(&pk).unwrap_err(), KemError::NonContributory);
+        assert_eq!(
+            DhKem::<X25519Dh>::decapsulate(&sk, &pk).unwrap_err(),
+            KemError::NonContributory
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use hybrid_kyber_crypto::dhkem::DhKem;
+use hybrid_kyber_crypto::traits::{Dh, Kem, KemError};
+use hybrid_kyber_crypto::x448::{x448, X448Dh, X448PublicKey, BASEPOINT, KEY_LEN};
+
+fn unhex(s: &str) -> [u8; KEY_LEN] {
+    let bytes: Vec<u8> = (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect();
+    bytes.try_into().unwrap()
+}
+
+/// RFC 7748 §5.2
+#[test]
+fn test_x448_function_vectors() {
+    let scalar = unhex("3d262fddf9ec8e88495266fea19a34d28882acef045104d0d1aae121700a779c984c24f8cdd78fbff44943eba368f54b29259a4f1c600ad3");
+    let u = unhex("06fce640fa3487bfda5f6cf2d5263f8aad88334cbd07437f020f08f9814dc031ddbdc38c19c6da2583fa5429db94ada18aa7a7fb4ef8a086");
+    let expected = unhex("ce3e4ff95a60dc6697da1db1d85e6afbdf79b50a2412d7546d5f239fe14fbaadeb445fc66a01b0779d98223961111e21766282f73dd96b6f");
+    assert_eq!(x448(&scalar, &u), expected);
+
+    let scalar = unhex("203d494428b8399352665ddca42f9de8fef600908e0d461cb021f8c538345dd77c3e4806e25f46d3315c44e0a5b4371282dd2c8d5be3095f");
+    let u = unhex("0fbcc2f993cd56d3305b0b7d9e55d4c1a8fb5dbb52f8e9a1e9b6201b165d015894e56c4d3570bee52fe205e28a78b91cdfbde71ce8d157db");
+    let expected = unhex("884a02576239ff7a2f2f63b2db6a9ff37047ac13568e1e30fe63c4a7ad1b3ee3a5700df34321d62077e63633c575c1c954514e99da7c179d");
+    assert_eq!(x448(&scalar, &u), expected);
+}
+
+/// RFC 7748 §5.2, one iteration of k = u = 5
+#[test]
+fn test_x448_iterated_once() {
+    let expected = unhex("3f482c8a9f19b01e6c46ee9711d9dc14fd4bf67af30765c2ae2b846a4d23a8cd0db897086239492caf350b51f833868b9bc2b3bca9cf4113");
+    assert_eq!(x448(&BASEPOINT, &BASEPOINT), expected);
+}
+
+/// RFC 7748 §5: a non-canonical u-coordinate is reduced modulo p, so p + 5 acts as 5
+#[test]
+fn test_x448_non_canonical_u_is_reduced() {
+    let alice_sk = unhex("9a8f4925d1519f5775cf46b04b5800d4ee9ee8bae8bc5565d498c28dd9c9baf574a9419744897391006382a6f127ab1d9ac2d8c0a598726b");
+    let p_plus_5 = unhex("04000000000000000000000000000000000000000000000000000000ffffffffffffffffffffffffffffffffffffffffffffffffffffffff");
+    assert_eq!(x448(&alice_sk, &p_plus_5), x448(&alice_sk, &BASEPOINT));
+}
+
+/// Unlike X25519, X448 masks no bits of u: bit 447 is part of the coordinate
+#[test]
+fn test_x448_high_bit_is_not_masked() {
+    let alice_sk = unhex("9a8f4925d1519f5775cf46b04b5800d4ee9ee8bae8bc5565d498c28dd9c9baf574a9419744897391006382a6f127ab1d9ac2d8c0a598726b");
+    let mut high = BASEPOINT;
+    high[KEY_LEN - 1] |= 0x80;
+    let expected = unhex("876b5d632a17411aa4f7fb50ec6a2b6e98ff3f500c60c346b796c6922741fc311d68c813706829cf77aa25c70d33de3f15dae61da4d0daf8");
+    assert_eq!(x448(&alice_sk, &high), expected);
+    assert_ne!(x448(&alice_sk, &high), x448(&alice_sk, &BASEPOINT));
+}
+
+/// RFC 7748 §6.2
+#[test]
+fn test_x448_diffie_hellman_vectors() {
+    let alice_sk = unhex("9a8f4925d1519f5775cf46b04b5800d4ee9ee8bae8bc5565d498c28dd9c9baf574a9419744897391006382a6f127ab1d9ac2d8c0a598726b");
+    let alice_pk = unhex("9b08f7cc31b7e3e67d22d5aea121074a273bd2b83de09c63faa73d2c22c5d9bbc836647241d953d40c5b12da88120d53177f80e532c41fa0");
+    let bob_sk = unhex("1c306a7ac2a0e2e0990b294470cba339e6453772b075811d8fad0d1d6927c120bb5ee8972b0d3e21374c9c921b09d1b0366f10b65173992d");
+    let bob_pk = unhex("3eb7a829b0cd20f5bcfc0b599b6feccf6da4627107bdb0d4f345b43027d8b972fc3e34fb4232a13ca706dcb57aec3dae07bdc1c67bf33609");
+    let shared = unhex("07fff4181ac6cc95ec1c16a94a0f74d12da232ce40a77552281d282bb60c0b56fd2464c335543936521c24403085d59a449a5037514a879d");
+
+    let alice = X448Dh::secret_key_from_bytes(&alice_sk).unwrap();
+    let bob = X448Dh::secret_key_from_bytes(&bob_sk).unwrap();
+    assert_eq!(X448Dh::public_key(&alice), X448PublicKey(alice_pk));
+    assert_eq!(X448Dh::public_key(&bob), X448PublicKey(bob_pk));
+    assert_eq!(X448Dh::diffie_hellman(&alice, &X448PublicKey(bob_pk)).as_bytes(), shared);
+    assert_eq!(X448Dh::diffie_hellman(&bob, &X448PublicKey(alice_pk)).as_bytes(), shared);
+}
+
+#[test]
+fn test_x448_random_agreement() {
+    let (pk_a, sk_a) = X448Dh::generate_keypair();
+    let (pk_b, sk_b) = X448Dh::generate_keypair();
+    assert_eq!(
+        X448Dh::diffie_hellman(&sk_a, &pk_b),
+        X448Dh::diffie_hellman(&sk_b, &pk_a)
+    );
+    assert!(X448Dh::public_key_from_bytes(&[0u8; 32]).is_none());
+}
+
+#[test]
+fn test_x448_low_order_points_rejected() {
+    type Kem448 = DhKem<X448Dh>;
+    let (_, sk) = Kem448::generate_keypair();
+    let mut one = [0u8; KEY_LEN];
+    one[0] = 1;
+    for point in [[0u8; KEY_LEN], one] {
+        let pk = Kem448::public_key_from_bytes(&point).unwrap();
+        assert_eq!(Kem448::encapsulate(&pk).unwrap_err(), KemError::NonContributory);
+        assert_eq!(Kem448::decapsulate(&sk, &pk).unwrap_err(), KemError::NonContributory);
+    }
+}
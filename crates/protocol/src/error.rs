@@ -0,0 +1,85 @@
+use thiserror::Error;
+
+use crate::framing::FrameError;
+use crate::handshake::HandshakeError;
+use crate::messages::{AlertDescription, MessageError};
+use crate::session::ChannelError;
+
+/// Which side of the connection an error is attributable to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorOrigin {
+    /// Our own state, configuration, API use or transport
+    Local,
+    /// Something the peer sent
+    Peer,
+}
+
+/// Any error this crate returns, for callers that drive a whole connection
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("framing: {0}")]
+    Frame(#[from] FrameError),
+    #[error("message: {0}")]
+    Message(#[from] MessageError),
+    #[error("handshake: {0}")]
+    Handshake(#[from] HandshakeError),
+    #[error("channel: {0}")]
+    Channel(#[from] ChannelError),
+}
+
+impl Error {
+    /// Whether the connection must be torn down. A non-fatal error leaves the channel usable:
+    /// the offending record was dropped or the request can be repeated later.
+    pub fn is_fatal(&self) -> bool {
+        !matches!(
+            self,
+            Error::Channel(
+                ChannelError::ReplayDetected
+                    | ChannelError::RekeyInProgress
+                    | ChannelError::RekeyUnsupported
+            )
+        )
+    }
+
+    /// Whether repeating the operation, or the connection, may succeed without changing
+    /// configuration: the failure was transient rather than a protocol violation
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Error::Frame(FrameError::Io(_)) | Error::Channel(ChannelError::RekeyInProgress)
+        )
+    }
+
+    /// The alert to send the peer before dropping the connection, if it should get one
+    pub fn alert(&self) -> Option<AlertDescription> {
+        match self {
+            Error::Frame(_) | Error::Handshake(HandshakeError::AlertReceived(_)) => None,
+            Error::Message(error) => Some(error.alert()),
+            Error::Handshake(error) => Some(error.alert()),
+            Error::Channel(error) => error.alert(),
+        }
+    }
+
+    pub fn origin(&self) -> ErrorOrigin {
+        let peer = match self {
+            Error::Frame(FrameError::Io(_)) => false,
+            Error::Frame(FrameError::TooLarge) => true,
+            Error::Message(_) => true,
+            Error::Handshake(error) => !matches!(
+                error,
+                HandshakeError::DecapsulationFailed
+                    | HandshakeError::EncapsulationFailed
+                    | HandshakeError::FinishedNotVerified
+            ),
+            Error::Channel(error) => !matches!(
+                error,
+                ChannelError::RekeyInProgress | ChannelError::RekeyUnsupported
+            ),
+        };
+        if peer {
+            ErrorOrigin::Peer
+        } else {
+            ErrorOrigin::Local
+        }
+    }
+}
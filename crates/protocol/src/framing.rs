@@ -1,28 +1,14 @@
+use thiserror::Error;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-#[derive(Debug)]
+#[derive(Debug, Error)]
 pub enum FrameError {
-    Io(std::io::Error),
+    #[error("IO error")]
+    Io(#[from] std::io::Error),
+    #[error("Frame too large")]
     TooLarge,
 }
 
-impl std::fmt::Display for FrameError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            FrameError::Io(e) => write!(f, "IO error: {}", e),
-            FrameError::TooLarge => write!(f, "Frame too large"),
-        }
-    }
-}
-
-impl std::error::Error for FrameError {}
-
-impl From<std::io::Error> for FrameError {
-    fn from(e: std::io::Error) -> Self {
-        FrameError::Io(e)
-    }
-}
-
 const MAX_FRAME_SIZE: u32 = 1024 * 1024; // 1MB max
 
 /// Write a length-prefixed frame
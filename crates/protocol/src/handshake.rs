@@ -1,120 +1,946 @@
-use crypto::hkdf::{derive_session_keys, SessionKeys};
-use crypto::kyber::Kyber768Kem;
-use crypto::traits::Kem;
-use crypto::x25519::X25519Kem;
-use pqcrypto_traits::kem::{Ciphertext as _, PublicKey as _};
+use std::fmt;
 
-use crate::messages::{ClientHello, ServerHello};
-use crate::transcript::compute_transcript;
+use crypto::dhkem::DhKem;
+use crypto::hkdf::{
+    derive_session_keys, derive_session_keys_with_hash, finished_mac, verify_finished_mac, ExportError, HashAlgorithm,
+    KeySchedule, SessionKeys, Sha256, Sha384,
+};
+use crypto::hybrid::{ConcatHkdf, HybridCiphertext, HybridKem, HybridPublicKey, HybridSecretKey};
+use crypto::kyber::{
+    Kyber1024Kem, Kyber512Kem, Kyber768Kem, MlKem1024Kem, MlKem512Kem, MlKem768Kem,
+};
+use crypto::p256::P256Dh;
+use crypto::secret::SecretBytes;
+use crypto::traits::{Dh, Kem, KemError};
+use crypto::x25519::X25519Dh;
+use crypto::x448::X448Dh;
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
+use thiserror::Error;
+use zeroize::Zeroizing;
 
-const PROTOCOL_VERSION: u8 = 1;
-const KYBER768_PK_SIZE: usize = 1184;
-const KYBER768_CT_SIZE: usize = 1088;
+use crate::messages::{
+    check_extensions, AlertDescription, CipherSuite, ClassicalGroup, ClientHello, DhShare,
+    Extension, Finished, KemAlgorithm, KemShare, LegacyClientHello, LegacyServerHello,
+    MessageError, ServerHello,
+};
+use crate::transcript::{
+    compute_channel_binding, compute_legacy_transcript, HandshakeType, Transcript,
+};
 
+pub const PROTOCOL_V1: u8 = 1;
+/// v2 derives session keys through the labeled `KeySchedule`
+pub const PROTOCOL_V2: u8 = 2;
+
+/// Versions offered by clients and accepted by servers by default, most preferred first
+pub const SUPPORTED_VERSIONS: &[u8] = &[PROTOCOL_V2, PROTOCOL_V1];
+
+/// Suites offered by clients and accepted by servers by default, most preferred first
+pub const DEFAULT_CIPHER_SUITES: &[CipherSuite] = &[
+    CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256,
+    CipherSuite::MLKEM768_X25519_AES256GCM_SHA256,
+    CipherSuite::KYBER768_X25519_CHACHA20POLY1305_SHA256,
+];
+
+/// Suites of the high-security profile, most preferred first
+pub const HIGH_SECURITY_CIPHER_SUITES: &[CipherSuite] = &[
+    CipherSuite::MLKEM1024_X448_AES256GCM_SHA384,
+    CipherSuite::MLKEM1024_X448_CHACHA20POLY1305_SHA384,
+];
+
+/// A named set of requirements on what a handshake may negotiate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityProfile {
+    /// Whatever the configured versions and suites allow
+    Standard,
+    /// ML-KEM-1024, X448 and the HKDF-SHA-384 key schedule, over protocol v2 or later
+    HighSecurity,
+}
+
+impl SecurityProfile {
+    pub fn permits_suite(self, suite: CipherSuite) -> bool {
+        match self {
+            SecurityProfile::Standard => true,
+            SecurityProfile::HighSecurity => HIGH_SECURITY_CIPHER_SUITES.contains(&suite),
+        }
+    }
+
+    pub fn permits_version(self, version: u8) -> bool {
+        match self {
+            SecurityProfile::Standard => true,
+            SecurityProfile::HighSecurity => version >= PROTOCOL_V2,
+        }
+    }
+}
+
+/// What one side of the handshake is willing to speak
+#[derive(Debug, Clone)]
+pub struct HandshakeConfig {
+    /// Protocol versions, most preferred first
+    pub versions: Vec<u8>,
+    /// Cipher suites, most preferred first
+    pub cipher_suites: Vec<CipherSuite>,
+    /// Extensions to place in this side's hello
+    pub extensions: Vec<Extension>,
+    /// Versions and suites outside the profile are never offered or accepted
+    pub profile: SecurityProfile,
+    /// Application context mixed into the transcript ahead of the hellos, like a Noise
+    /// prologue. It is never sent: both sides must be configured with the same bytes, or
+    /// the handshake fails with `InvalidFinished`.
+    pub context: Vec<u8>,
+}
+
+impl Default for HandshakeConfig {
+    fn default() -> Self {
+        Self {
+            versions: SUPPORTED_VERSIONS.to_vec(),
+            cipher_suites: DEFAULT_CIPHER_SUITES.to_vec(),
+            extensions: Vec::new(),
+            profile: SecurityProfile::Standard,
+            context: Vec::new(),
+        }
+    }
+}
+
+impl HandshakeConfig {
+    /// A client that demands, or a server that enforces, the high-security profile
+    pub fn high_security() -> Self {
+        Self {
+            versions: vec![PROTOCOL_V2],
+            cipher_suites: HIGH_SECURITY_CIPHER_SUITES.to_vec(),
+            extensions: Vec::new(),
+            profile: SecurityProfile::HighSecurity,
+            context: Vec::new(),
+        }
+    }
+
+    /// The configured versions that the profile permits
+    fn permitted_versions(&self) -> Vec<u8> {
+        let profile = self.profile;
+        self.versions
+            .iter()
+            .copied()
+            .filter(|&version| profile.permits_version(version))
+            .collect()
+    }
+}
+
+/// A KEM implementation that can be negotiated under a `KemAlgorithm` code point.
+///
+/// Implement this to run the handshake over your own KEM, or your own implementation of a
+/// built-in parameter set, through the `*_with` functions. A new KEM claims an id of its
+/// own, such as `KemAlgorithm(0xfe00)`, and suites naming it in `HandshakeConfig`.
+pub trait NamedKem: Kem<SharedSecret = SecretBytes, PublicKey: Clone> {
+    const ALGORITHM: KemAlgorithm;
+}
+
+/// A Diffie-Hellman implementation negotiated under a `ClassicalGroup` code point
+pub trait NamedGroup: Dh<PublicKey: Clone, SharedSecret: AsRef<[u8]>> {
+    const GROUP: ClassicalGroup;
+}
+
+impl NamedKem for Kyber512Kem {
+    const ALGORITHM: KemAlgorithm = KemAlgorithm::KYBER512;
+}
+
+impl NamedKem for Kyber768Kem {
+    const ALGORITHM: KemAlgorithm = KemAlgorithm::KYBER768;
+}
+
+impl NamedKem for Kyber1024Kem {
+    const ALGORITHM: KemAlgorithm = KemAlgorithm::KYBER1024;
+}
+
+impl NamedKem for MlKem512Kem {
+    const ALGORITHM: KemAlgorithm = KemAlgorithm::MLKEM512;
+}
+
+impl NamedKem for MlKem768Kem {
+    const ALGORITHM: KemAlgorithm = KemAlgorithm::MLKEM768;
+}
+
+impl NamedKem for MlKem1024Kem {
+    const ALGORITHM: KemAlgorithm = KemAlgorithm::MLKEM1024;
+}
+
+impl NamedGroup for X25519Dh {
+    const GROUP: ClassicalGroup = ClassicalGroup::X25519;
+}
+
+impl NamedGroup for P256Dh {
+    const GROUP: ClassicalGroup = ClassicalGroup::P256;
+}
+
+impl NamedGroup for X448Dh {
+    const GROUP: ClassicalGroup = ClassicalGroup::X448;
+}
+
+/// The client's offer and the secret key behind each share, held until the ServerHello arrives
 pub struct ClientHandshakeState {
     pub client_hello: ClientHello,
-    kyber_sk: <Kyber768Kem as Kem>::SecretKey,
-    x25519_sk: <X25519Kem as Kem>::SecretKey,
-    x25519_pk: <X25519Kem as Kem>::PublicKey,
+    kem_sks: Vec<(KemAlgorithm, SecretBytes)>,
+    dh_sks: Vec<(ClassicalGroup, SecretBytes)>,
+    context: Vec<u8>,
+}
+
+impl fmt::Debug for ClientHandshakeState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientHandshakeState")
+            .field("client_hello", &self.client_hello)
+            .finish_non_exhaustive()
+    }
 }
 
 pub struct Session {
     pub keys: SessionKeys,
-    pub transcript: [u8; 32],
+    /// Transcript hash through both hellos, under the suite's KDF hash
+    pub transcript: Vec<u8>,
+    pub version: u8,
+    pub cipher_suite: CipherSuite,
+    /// Extensions the peer sent in its hello, including ones this side does not know
+    pub peer_extensions: Vec<Extension>,
     pub is_client: bool,
+    key_schedule: KeySchedule,
+    /// Running transcript through both hellos; the Finished messages are absorbed on top
+    hello_transcript: Transcript,
+    peer_finished_verified: bool,
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("version", &self.version)
+            .field("cipher_suite", &self.cipher_suite)
+            .field("is_client", &self.is_client)
+            .field("peer_finished_verified", &self.peer_finished_verified)
+            .finish_non_exhaustive()
+    }
 }
 
-#[derive(Debug)]
+/// Keys for a connection in the original v1 wire format. That format has no Finished
+/// messages, so there is nothing to verify before `LegacyChannel` uses them.
+pub struct LegacySession {
+    pub keys: SessionKeys,
+    /// SHA-256 over both legacy hellos
+    pub transcript: [u8; 32],
+}
+
+impl fmt::Debug for LegacySession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LegacySession").finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Error)]
 pub enum HandshakeError {
-    InvalidVersion,
+    #[error("no protocol version in common with the peer")]
+    NoCommonVersion,
+    #[error("the server selected a version the client did not offer")]
+    UnofferedVersion,
+    #[error("a key share or ciphertext has the wrong size or encoding")]
     InvalidKeySize,
+    #[error("decapsulation failed")]
     DecapsulationFailed,
+    #[error("no cipher suite in common with the peer")]
+    NoCommonSuite,
+    #[error("the server selected a suite the client did not offer")]
+    UnofferedSuite,
+    #[error("invalid hello extensions")]
+    InvalidExtensions(#[source] MessageError),
+    /// The peer's Finished MAC did not match: it derived different keys, saw a different
+    /// transcript or was configured with a different application context
+    #[error("the peer's Finished did not verify")]
+    InvalidFinished,
+    /// The session was used before the peer's Finished was verified
+    #[error("the peer's Finished has not been verified")]
+    FinishedNotVerified,
+    #[error("encapsulation failed")]
+    EncapsulationFailed,
+    /// The peer's X25519 or X448 share has small order, so the DH output would be all zeros
+    #[error("the peer's DH share has small order")]
+    LowOrderPoint,
+    /// The client's KEM public key has a coefficient not reduced mod q (FIPS 203 §7.2)
+    #[error("the client's KEM public key failed the modulus check")]
+    KemModulusCheckFailed,
+    /// The peer aborted the handshake with this alert
+    #[error("the peer sent alert {0:?}")]
+    AlertReceived(AlertDescription),
+    #[error("malformed handshake record")]
+    InvalidMessage(#[source] MessageError),
+    /// A record other than a handshake message or alert arrived before the channel was up
+    #[error("unexpected record during the handshake")]
+    UnexpectedMessage,
+}
+
+impl HandshakeError {
+    /// The alert that tells the peer why the handshake was aborted
+    pub fn alert(&self) -> AlertDescription {
+        match self {
+            HandshakeError::NoCommonVersion | HandshakeError::UnofferedVersion => {
+                AlertDescription::ProtocolVersion
+            }
+            HandshakeError::NoCommonSuite => AlertDescription::HandshakeFailure,
+            HandshakeError::InvalidKeySize
+            | HandshakeError::UnofferedSuite
+            | HandshakeError::LowOrderPoint
+            | HandshakeError::KemModulusCheckFailed => AlertDescription::IllegalParameter,
+            HandshakeError::InvalidExtensions(error) | HandshakeError::InvalidMessage(error) => {
+                error.alert()
+            }
+            HandshakeError::UnexpectedMessage => AlertDescription::UnexpectedMessage,
+            HandshakeError::InvalidFinished => AlertDescription::DecryptError,
+            HandshakeError::DecapsulationFailed
+            | HandshakeError::EncapsulationFailed
+            | HandshakeError::FinishedNotVerified
+            | HandshakeError::AlertReceived(_) => AlertDescription::InternalError,
+        }
+    }
+}
+
+impl From<KemError> for HandshakeError {
+    fn from(error: KemError) -> Self {
+        match error {
+            KemError::InvalidPublicKey | KemError::InvalidCiphertext => HandshakeError::InvalidKeySize,
+            KemError::InvalidSecretKey | KemError::DecapsulationFailed => {
+                HandshakeError::DecapsulationFailed
+            }
+            KemError::EncapsulationFailed => HandshakeError::EncapsulationFailed,
+            KemError::ModulusCheckFailed => HandshakeError::KemModulusCheckFailed,
+            KemError::NonContributory => HandshakeError::LowOrderPoint,
+        }
+    }
+}
+
+/// The post-quantum KEM `K` alongside the DH group `D`, merged by concat-then-HKDF under `H`
+type Hybrid<K, D, H> = HybridKem<K, DhKem<D>, ConcatHkdf<H>>;
+
+/// PQ ciphertext, DH ciphertext (an ephemeral public key) and the combined shared secret
+type Encapsulation = (Vec<u8>, Vec<u8>, SecretBytes);
+
+fn keypair_with<K: NamedKem, R: CryptoRng + RngCore>(rng: &mut R) -> (Vec<u8>, SecretBytes) {
+    let (pk, sk) = K::generate_keypair_with_rng(rng);
+    (K::public_key_to_bytes(&pk), K::secret_key_to_bytes(&sk))
+}
+
+fn dh_keypair_with<D: NamedGroup, R: CryptoRng + RngCore>(rng: &mut R) -> (Vec<u8>, SecretBytes) {
+    let (pk, sk) = D::generate_keypair_with_rng(rng);
+    (D::public_key_to_bytes(&pk), D::secret_key_to_bytes(&sk))
+}
+
+fn encapsulate_with<K: NamedKem, D: NamedGroup, R: CryptoRng + RngCore>(
+    hash: HashAlgorithm,
+    pk: &[u8],
+    dh_pk: &[u8],
+    rng: &mut R,
+) -> Result<Encapsulation, HandshakeError> {
+    if pk.len() != K::PUBLIC_KEY_SIZE || dh_pk.len() != D::PUBLIC_KEY_SIZE {
+        return Err(HandshakeError::InvalidKeySize);
+    }
+    let pk = HybridPublicKey {
+        a: K::public_key_from_bytes(pk)?,
+        b: D::public_key_from_bytes(dh_pk).ok_or(HandshakeError::InvalidKeySize)?,
+    };
+    let (ct, ss) = match hash {
+        HashAlgorithm::Sha256 => Hybrid::<K, D, Sha256>::encapsulate_with_rng(&pk, rng)?,
+        HashAlgorithm::Sha384 => Hybrid::<K, D, Sha384>::encapsulate_with_rng(&pk, rng)?,
+    };
+    Ok((K::ciphertext_to_bytes(&ct.a), D::public_key_to_bytes(&ct.b), ss))
+}
+
+fn decapsulate_with<K: NamedKem, D: NamedGroup>(
+    hash: HashAlgorithm,
+    keys: &HybridKeys,
+    ct: &[u8],
+    dh_ct: &[u8],
+) -> Result<SecretBytes, HandshakeError> {
+    if ct.len() != K::CIPHERTEXT_SIZE || dh_ct.len() != D::PUBLIC_KEY_SIZE {
+        return Err(HandshakeError::InvalidKeySize);
+    }
+    let sk = HybridSecretKey::<K, DhKem<D>> {
+        a: K::secret_key_from_bytes(&keys.kem_sk)?,
+        b: D::secret_key_from_bytes(&keys.dh_sk).ok_or(HandshakeError::DecapsulationFailed)?,
+        public: HybridPublicKey {
+            a: K::public_key_from_bytes(&keys.kem_pk)?,
+            b: D::public_key_from_bytes(&keys.dh_pk).ok_or(HandshakeError::InvalidKeySize)?,
+        },
+    };
+    let ct = HybridCiphertext {
+        a: K::ciphertext_from_bytes(ct)?,
+        b: D::public_key_from_bytes(dh_ct).ok_or(HandshakeError::InvalidKeySize)?,
+    };
+    let ss = match hash {
+        HashAlgorithm::Sha256 => Hybrid::<K, D, Sha256>::decapsulate(&sk, &ct)?,
+        HashAlgorithm::Sha384 => Hybrid::<K, D, Sha384>::decapsulate(&sk, &ct)?,
+    };
+    Ok(ss)
+}
+
+/// One side's secret material for a hybrid exchange, in wire encoding; the secret halves
+/// wipe themselves on drop
+pub(crate) struct HybridKeys {
+    pub kem_pk: Vec<u8>,
+    pub kem_sk: SecretBytes,
+    pub dh_pk: Vec<u8>,
+    pub dh_sk: SecretBytes,
+}
+
+/// Whether the built-in primitives can run `suite`; the negotiating functions only offer
+/// and select these
+pub(crate) fn is_builtin(suite: &CipherSuite) -> bool {
+    suite.kem.public_key_size().is_some() && suite.group.public_key_size().is_some()
+}
+
+/// Key pair for a built-in KEM; callers only pass code points `is_builtin` accepted
+pub(crate) fn kem_keypair<R: CryptoRng + RngCore>(
+    kem: KemAlgorithm,
+    rng: &mut R,
+) -> (Vec<u8>, SecretBytes) {
+    match kem {
+        KemAlgorithm::KYBER512 => keypair_with::<Kyber512Kem, R>(rng),
+        KemAlgorithm::KYBER768 => keypair_with::<Kyber768Kem, R>(rng),
+        KemAlgorithm::KYBER1024 => keypair_with::<Kyber1024Kem, R>(rng),
+        KemAlgorithm::MLKEM512 => keypair_with::<MlKem512Kem, R>(rng),
+        KemAlgorithm::MLKEM768 => keypair_with::<MlKem768Kem, R>(rng),
+        KemAlgorithm::MLKEM1024 => keypair_with::<MlKem1024Kem, R>(rng),
+        other => unreachable!("{other:?} is not a built-in KEM"),
+    }
+}
+
+pub(crate) fn dh_keypair<R: CryptoRng + RngCore>(
+    group: ClassicalGroup,
+    rng: &mut R,
+) -> (Vec<u8>, SecretBytes) {
+    match group {
+        ClassicalGroup::X25519 => dh_keypair_with::<X25519Dh, R>(rng),
+        ClassicalGroup::P256 => dh_keypair_with::<P256Dh, R>(rng),
+        ClassicalGroup::X448 => dh_keypair_with::<X448Dh, R>(rng),
+        other => unreachable!("{other:?} is not a built-in group"),
+    }
+}
+
+fn encapsulate_in_group<K: NamedKem, R: CryptoRng + RngCore>(
+    suite: CipherSuite,
+    pk: &[u8],
+    dh_pk: &[u8],
+    rng: &mut R,
+) -> Result<Encapsulation, HandshakeError> {
+    let hash = suite.kdf.hash_algorithm();
+    match suite.group {
+        ClassicalGroup::X25519 => encapsulate_with::<K, X25519Dh, R>(hash, pk, dh_pk, rng),
+        ClassicalGroup::P256 => encapsulate_with::<K, P256Dh, R>(hash, pk, dh_pk, rng),
+        ClassicalGroup::X448 => encapsulate_with::<K, X448Dh, R>(hash, pk, dh_pk, rng),
+        _ => Err(HandshakeError::NoCommonSuite),
+    }
+}
+
+fn decapsulate_in_group<K: NamedKem>(
+    suite: CipherSuite,
+    keys: &HybridKeys,
+    ct: &[u8],
+    dh_ct: &[u8],
+) -> Result<SecretBytes, HandshakeError> {
+    let hash = suite.kdf.hash_algorithm();
+    match suite.group {
+        ClassicalGroup::X25519 => decapsulate_with::<K, X25519Dh>(hash, keys, ct, dh_ct),
+        ClassicalGroup::P256 => decapsulate_with::<K, P256Dh>(hash, keys, ct, dh_ct),
+        ClassicalGroup::X448 => decapsulate_with::<K, X448Dh>(hash, keys, ct, dh_ct),
+        _ => Err(HandshakeError::UnofferedSuite),
+    }
+}
+
+/// Encapsulate to a PQ public key and a public key in the suite's classical group at once
+pub(crate) fn hybrid_encapsulate<R: CryptoRng + RngCore>(
+    suite: CipherSuite,
+    pk: &[u8],
+    dh_pk: &[u8],
+    rng: &mut R,
+) -> Result<Encapsulation, HandshakeError> {
+    match suite.kem {
+        KemAlgorithm::KYBER512 => encapsulate_in_group::<Kyber512Kem, R>(suite, pk, dh_pk, rng),
+        KemAlgorithm::KYBER768 => encapsulate_in_group::<Kyber768Kem, R>(suite, pk, dh_pk, rng),
+        KemAlgorithm::KYBER1024 => encapsulate_in_group::<Kyber1024Kem, R>(suite, pk, dh_pk, rng),
+        KemAlgorithm::MLKEM512 => encapsulate_in_group::<MlKem512Kem, R>(suite, pk, dh_pk, rng),
+        KemAlgorithm::MLKEM768 => encapsulate_in_group::<MlKem768Kem, R>(suite, pk, dh_pk, rng),
+        KemAlgorithm::MLKEM1024 => encapsulate_in_group::<MlKem1024Kem, R>(suite, pk, dh_pk, rng),
+        _ => Err(HandshakeError::NoCommonSuite),
+    }
+}
+
+pub(crate) fn hybrid_decapsulate(
+    suite: CipherSuite,
+    keys: &HybridKeys,
+    ct: &[u8],
+    dh_ct: &[u8],
+) -> Result<SecretBytes, HandshakeError> {
+    match suite.kem {
+        KemAlgorithm::KYBER512 => decapsulate_in_group::<Kyber512Kem>(suite, keys, ct, dh_ct),
+        KemAlgorithm::KYBER768 => decapsulate_in_group::<Kyber768Kem>(suite, keys, ct, dh_ct),
+        KemAlgorithm::KYBER1024 => decapsulate_in_group::<Kyber1024Kem>(suite, keys, ct, dh_ct),
+        KemAlgorithm::MLKEM512 => decapsulate_in_group::<MlKem512Kem>(suite, keys, ct, dh_ct),
+        KemAlgorithm::MLKEM768 => decapsulate_in_group::<MlKem768Kem>(suite, keys, ct, dh_ct),
+        KemAlgorithm::MLKEM1024 => decapsulate_in_group::<MlKem1024Kem>(suite, keys, ct, dh_ct),
+        _ => Err(HandshakeError::UnofferedSuite),
+    }
+}
+
+fn session_keys(version: u8, shared_secret: &[u8], key_schedule: &KeySchedule) -> SessionKeys {
+    match version {
+        // The hybrid secret arrives already combined, so the classical half is empty
+        PROTOCOL_V1 => derive_session_keys_with_hash(
+            key_schedule.hash(),
+            shared_secret,
+            &[],
+            key_schedule.transcript(),
+        ),
+        _ => key_schedule.session_keys(),
+    }
+}
+
+/// Pick the first suite in `preferences` that the client offered both key shares for
+fn select_cipher_suite(client_hello: &ClientHello, preferences: &[CipherSuite]) -> Option<CipherSuite> {
+    preferences.iter().copied().find(|suite| {
+        client_hello.cipher_suites.contains(suite)
+            && client_hello.kem_share(suite.kem).is_some()
+            && client_hello.dh_share(suite.group).is_some()
+    })
+}
+
+/// The suites in `cipher_suites` that `profile` permits
+fn permitted_suites(profile: SecurityProfile, cipher_suites: &[CipherSuite]) -> Vec<CipherSuite> {
+    cipher_suites
+        .iter()
+        .copied()
+        .filter(|&suite| profile.permits_suite(suite))
+        .collect()
+}
+
+/// The suites in `config` that the built-in primitives can run
+fn builtin_suites(config: &HandshakeConfig) -> Vec<CipherSuite> {
+    config.cipher_suites.iter().copied().filter(is_builtin).collect()
+}
+
+/// The suites in `config` that `K` and `D` can run
+fn suites_for<K: NamedKem, D: NamedGroup>(config: &HandshakeConfig) -> Vec<CipherSuite> {
+    config
+        .cipher_suites
+        .iter()
+        .copied()
+        .filter(|suite| suite.kem == K::ALGORITHM && suite.group == D::GROUP)
+        .collect()
 }
 
-pub fn generate_client_hello() -> (ClientHello, ClientHandshakeState) {
-    let (kyber_pk, kyber_sk) = Kyber768Kem::generate_keypair();
-    let (x25519_pk, x25519_sk) = X25519Kem::generate_keypair();
+fn build_client_hello<R: CryptoRng + RngCore>(
+    config: &HandshakeConfig,
+    cipher_suites: Vec<CipherSuite>,
+    rng: &mut R,
+    kem_keypair: impl Fn(KemAlgorithm, &mut R) -> (Vec<u8>, SecretBytes),
+    dh_keypair: impl Fn(ClassicalGroup, &mut R) -> (Vec<u8>, SecretBytes),
+) -> (ClientHello, ClientHandshakeState) {
+    // Offering only what the profile permits is how a client demands it
+    let cipher_suites = permitted_suites(config.profile, &cipher_suites);
+    let mut kem_shares: Vec<KemShare> = Vec::new();
+    let mut kem_sks = Vec::new();
+    let mut dh_shares: Vec<DhShare> = Vec::new();
+    let mut dh_sks = Vec::new();
+    for suite in &cipher_suites {
+        if !kem_shares.iter().any(|share| share.kem == suite.kem) {
+            let (public_key, sk) = kem_keypair(suite.kem, rng);
+            kem_shares.push(KemShare {
+                kem: suite.kem,
+                public_key,
+            });
+            kem_sks.push((suite.kem, sk));
+        }
+        if !dh_shares.iter().any(|share| share.group == suite.group) {
+            let (public_key, sk) = dh_keypair(suite.group, rng);
+            dh_shares.push(DhShare {
+                group: suite.group,
+                public_key,
+            });
+            dh_sks.push((suite.group, sk));
+        }
+    }
 
     let client_hello = ClientHello {
-        version: PROTOCOL_VERSION,
-        kyber_pk: kyber_pk.as_bytes().to_vec(),
-        x25519_pk: x25519_pk.to_bytes(),
+        versions: config.permitted_versions(),
+        cipher_suites,
+        kem_shares,
+        dh_shares,
+        extensions: config.extensions.clone(),
     };
 
     let state = ClientHandshakeState {
         client_hello: client_hello.clone(),
-        kyber_sk,
-        x25519_sk,
-        x25519_pk,
+        kem_sks,
+        dh_sks,
+        context: config.context.clone(),
     };
 
     (client_hello, state)
 }
 
-pub fn handle_client_hello(
+fn build_server_hello(
     client_hello: ClientHello,
+    config: &HandshakeConfig,
+    cipher_suites: &[CipherSuite],
+    encapsulate: impl FnOnce(CipherSuite, &[u8], &[u8]) -> Result<Encapsulation, HandshakeError>,
 ) -> Result<(ServerHello, Session), HandshakeError> {
-    if client_hello.version != PROTOCOL_VERSION {
-        return Err(HandshakeError::InvalidVersion);
-    }
-    if client_hello.kyber_pk.len() != KYBER768_PK_SIZE {
-        return Err(HandshakeError::InvalidKeySize);
-    }
-
-    let client_kyber_pk =
-        pqcrypto_kyber::kyber768::PublicKey::from_bytes(&client_hello.kyber_pk)
-            .map_err(|_| HandshakeError::InvalidKeySize)?;
-    let client_x25519_pk = x25519_dalek::PublicKey::from(client_hello.x25519_pk);
+    check_extensions(&client_hello.extensions).map_err(HandshakeError::InvalidExtensions)?;
 
-    let (server_x25519_pk, server_x25519_sk) = X25519Kem::generate_keypair();
+    let version = config
+        .permitted_versions()
+        .into_iter()
+        .find(|v| client_hello.versions.contains(v))
+        .ok_or(HandshakeError::NoCommonVersion)?;
 
-    let (kyber_ct, ss_pq) = Kyber768Kem::encapsulate(&client_kyber_pk);
+    let cipher_suites = permitted_suites(config.profile, cipher_suites);
+    let cipher_suite = select_cipher_suite(&client_hello, &cipher_suites)
+        .ok_or(HandshakeError::NoCommonSuite)?;
+    let kem_share = client_hello
+        .kem_share(cipher_suite.kem)
+        .ok_or(HandshakeError::NoCommonSuite)?;
+    let dh_share = client_hello
+        .dh_share(cipher_suite.group)
+        .ok_or(HandshakeError::NoCommonSuite)?;
 
-    let dh = server_x25519_sk.diffie_hellman(&client_x25519_pk);
-    let ss_classical = dh.as_bytes().to_vec();
+    // Share sizes are checked against the selected primitives. The DH "ciphertext" is the
+    // server's ephemeral public key.
+    let (kyber_ct, dh_ct, shared_secret) =
+        encapsulate(cipher_suite, &kem_share.public_key, &dh_share.public_key)?;
 
     let server_hello = ServerHello {
-        kyber_ct: kyber_ct.as_bytes().to_vec(),
-        x25519_pk: server_x25519_pk.to_bytes(),
+        version,
+        cipher_suite,
+        kyber_ct,
+        dh_public_key: dh_ct,
+        extensions: config.extensions.clone(),
     };
 
-    let transcript = compute_transcript(&client_hello, &server_hello);
-    let keys = derive_session_keys(&ss_pq, &ss_classical, &transcript);
+    let hello_transcript = Transcript::from_hellos(&config.context, &client_hello, &server_hello);
+    let transcript = hello_transcript.snapshot();
+    let key_schedule =
+        KeySchedule::new(cipher_suite.kdf.hash_algorithm(), &shared_secret, &transcript);
+    let keys = session_keys(version, &shared_secret, &key_schedule);
 
     Ok((
         server_hello,
         Session {
             keys,
             transcript,
+            version,
+            cipher_suite,
+            peer_extensions: client_hello.extensions,
             is_client: false,
+            key_schedule,
+            hello_transcript,
+            peer_finished_verified: false,
         },
     ))
 }
 
-pub fn handle_server_hello(
+fn finish_client_handshake(
     server_hello: ServerHello,
     state: ClientHandshakeState,
+    decapsulate: impl FnOnce(CipherSuite, &HybridKeys, &[u8], &[u8]) -> Result<SecretBytes, HandshakeError>,
 ) -> Result<Session, HandshakeError> {
-    if server_hello.kyber_ct.len() != KYBER768_CT_SIZE {
-        return Err(HandshakeError::InvalidKeySize);
-    }
-
-    let kyber_ct =
-        pqcrypto_kyber::kyber768::Ciphertext::from_bytes(&server_hello.kyber_ct)
-            .map_err(|_| HandshakeError::InvalidKeySize)?;
+    check_extensions(&server_hello.extensions).map_err(HandshakeError::InvalidExtensions)?;
 
-    let ss_pq = Kyber768Kem::decapsulate(&state.kyber_sk, &kyber_ct);
+    let version = server_hello.version;
+    if !state.client_hello.versions.contains(&version) {
+        return Err(HandshakeError::UnofferedVersion);
+    }
+    let cipher_suite = server_hello.cipher_suite;
+    if !state.client_hello.cipher_suites.contains(&cipher_suite) {
+        return Err(HandshakeError::UnofferedSuite);
+    }
+    let kem = cipher_suite.kem;
+    let group = cipher_suite.group;
 
-    let server_x25519_pk = x25519_dalek::PublicKey::from(server_hello.x25519_pk);
-    let dh = state.x25519_sk.diffie_hellman(&server_x25519_pk);
-    let ss_classical = dh.as_bytes().to_vec();
+    let (_, kem_sk) = state
+        .kem_sks
+        .iter()
+        .find(|(offered, _)| *offered == kem)
+        .ok_or(HandshakeError::UnofferedSuite)?;
+    let kem_share = state
+        .client_hello
+        .kem_share(kem)
+        .ok_or(HandshakeError::UnofferedSuite)?;
+    let (_, dh_sk) = state
+        .dh_sks
+        .iter()
+        .find(|(offered, _)| *offered == group)
+        .ok_or(HandshakeError::UnofferedSuite)?;
+    let dh_share = state
+        .client_hello
+        .dh_share(group)
+        .ok_or(HandshakeError::UnofferedSuite)?;
+    let keys = HybridKeys {
+        kem_pk: kem_share.public_key.clone(),
+        kem_sk: kem_sk.clone(),
+        dh_pk: dh_share.public_key.clone(),
+        dh_sk: dh_sk.clone(),
+    };
+    let shared_secret =
+        decapsulate(cipher_suite, &keys, &server_hello.kyber_ct, &server_hello.dh_public_key)?;
 
-    let transcript = compute_transcript(&state.client_hello, &server_hello);
-    let keys = derive_session_keys(&ss_pq, &ss_classical, &transcript);
+    let hello_transcript =
+        Transcript::from_hellos(&state.context, &state.client_hello, &server_hello);
+    let transcript = hello_transcript.snapshot();
+    let key_schedule =
+        KeySchedule::new(cipher_suite.kdf.hash_algorithm(), &shared_secret, &transcript);
+    let keys = session_keys(version, &shared_secret, &key_schedule);
 
     Ok(Session {
         keys,
         transcript,
+        version,
+        cipher_suite,
+        peer_extensions: server_hello.extensions,
         is_client: true,
+        key_schedule,
+        hello_transcript,
+        peer_finished_verified: false,
+    })
+}
+
+/// Offer every configured suite, with one key share per distinct KEM and classical group
+pub fn generate_client_hello(config: &HandshakeConfig) -> (ClientHello, ClientHandshakeState) {
+    generate_client_hello_with_rng(config, &mut OsRng)
+}
+
+/// Like `generate_client_hello`, drawing every key share from `rng`
+pub fn generate_client_hello_with_rng<R: CryptoRng + RngCore>(
+    config: &HandshakeConfig,
+    rng: &mut R,
+) -> (ClientHello, ClientHandshakeState) {
+    build_client_hello(config, builtin_suites(config), rng, kem_keypair, dh_keypair)
+}
+
+/// Answer a ClientHello with the server's most preferred version and suite that the client offered
+pub fn handle_client_hello(
+    client_hello: ClientHello,
+    config: &HandshakeConfig,
+) -> Result<(ServerHello, Session), HandshakeError> {
+    handle_client_hello_with_rng(client_hello, config, &mut OsRng)
+}
+
+/// Like `handle_client_hello`, drawing the encapsulation randomness from `rng`
+pub fn handle_client_hello_with_rng<R: CryptoRng + RngCore>(
+    client_hello: ClientHello,
+    config: &HandshakeConfig,
+    rng: &mut R,
+) -> Result<(ServerHello, Session), HandshakeError> {
+    build_server_hello(client_hello, config, &builtin_suites(config), |suite, pk, dh_pk| {
+        hybrid_encapsulate(suite, pk, dh_pk, rng)
     })
 }
+
+pub fn handle_server_hello(
+    server_hello: ServerHello,
+    state: ClientHandshakeState,
+) -> Result<Session, HandshakeError> {
+    finish_client_handshake(server_hello, state, hybrid_decapsulate)
+}
+
+/// Like `generate_client_hello`, but running caller-supplied primitives: only the
+/// configured suites naming `K::ALGORITHM` and `D::GROUP` are offered
+pub fn generate_client_hello_with<K: NamedKem, D: NamedGroup>(
+    config: &HandshakeConfig,
+) -> (ClientHello, ClientHandshakeState) {
+    generate_client_hello_with_rng_using::<K, D, _>(config, &mut OsRng)
+}
+
+/// `generate_client_hello_with`, drawing both key shares from `rng`
+pub fn generate_client_hello_with_rng_using<K: NamedKem, D: NamedGroup, R: CryptoRng + RngCore>(
+    config: &HandshakeConfig,
+    rng: &mut R,
+) -> (ClientHello, ClientHandshakeState) {
+    build_client_hello(
+        config,
+        suites_for::<K, D>(config),
+        rng,
+        |_, rng| keypair_with::<K, _>(rng),
+        |_, rng| dh_keypair_with::<D, _>(rng),
+    )
+}
+
+/// Like `handle_client_hello`, but only selecting suites that `K` and `D` can run
+pub fn handle_client_hello_with<K: NamedKem, D: NamedGroup>(
+    client_hello: ClientHello,
+    config: &HandshakeConfig,
+) -> Result<(ServerHello, Session), HandshakeError> {
+    handle_client_hello_with_rng_using::<K, D, _>(client_hello, config, &mut OsRng)
+}
+
+/// `handle_client_hello_with`, drawing the encapsulation randomness from `rng`
+pub fn handle_client_hello_with_rng_using<K: NamedKem, D: NamedGroup, R: CryptoRng + RngCore>(
+    client_hello: ClientHello,
+    config: &HandshakeConfig,
+    rng: &mut R,
+) -> Result<(ServerHello, Session), HandshakeError> {
+    build_server_hello(client_hello, config, &suites_for::<K, D>(config), |suite, pk, dh_pk| {
+        encapsulate_with::<K, D, _>(suite.kdf.hash_algorithm(), pk, dh_pk, rng)
+    })
+}
+
+pub fn handle_server_hello_with<K: NamedKem, D: NamedGroup>(
+    server_hello: ServerHello,
+    state: ClientHandshakeState,
+) -> Result<Session, HandshakeError> {
+    if server_hello.cipher_suite.kem != K::ALGORITHM || server_hello.cipher_suite.group != D::GROUP {
+        return Err(HandshakeError::UnofferedSuite);
+    }
+    finish_client_handshake(server_hello, state, |suite, keys, ct, dh_ct| {
+        decapsulate_with::<K, D>(suite.kdf.hash_algorithm(), keys, ct, dh_ct)
+    })
+}
+
+impl Session {
+    /// This side's Finished message, sent after the hellos
+    pub fn finished(&self) -> Finished {
+        let (client, server) = self.finished_pair();
+        if self.is_client {
+            client
+        } else {
+            server
+        }
+    }
+
+    /// The server's Finished covers the hellos; the client's also covers the server Finished
+    fn finished_pair(&self) -> (Finished, Finished) {
+        let finished_keys = self.key_schedule.finished_keys();
+        let server = Finished {
+            verify_data: finished_mac(self.hash(), &finished_keys.server, &self.transcript),
+        };
+        let client = Finished {
+            verify_data: finished_mac(
+                self.hash(),
+                &finished_keys.client,
+                &self.transcript_through(&server).snapshot(),
+            ),
+        };
+        (client, server)
+    }
+
+    /// The running transcript with the server's Finished absorbed
+    fn transcript_through(&self, server_finished: &Finished) -> Transcript {
+        let mut transcript = self.hello_transcript.clone();
+        transcript.absorb(HandshakeType::Finished, &server_finished.to_bytes());
+        transcript
+    }
+
+    /// Check the peer's Finished; the session may only carry data once this succeeds
+    pub fn verify_finished(&mut self, finished: &Finished) -> Result<(), HandshakeError> {
+        let finished_keys = self.key_schedule.finished_keys();
+        let verified = if self.is_client {
+            verify_finished_mac(
+                self.hash(),
+                &finished_keys.server,
+                &self.transcript,
+                &finished.verify_data,
+            )
+        } else {
+            let (_, server) = self.finished_pair();
+            verify_finished_mac(
+                self.hash(),
+                &finished_keys.client,
+                &self.transcript_through(&server).snapshot(),
+                &finished.verify_data,
+            )
+        };
+        if !verified {
+            return Err(HandshakeError::InvalidFinished);
+        }
+        self.peer_finished_verified = true;
+        Ok(())
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        self.peer_finished_verified
+    }
+
+    /// Keying material for the application, independent of the traffic keys (RFC 8446 §7.5).
+    /// Fails if `label` exceeds `MAX_EXPORTER_LABEL_LEN` or `len` exceeds 255 hash lengths.
+    pub fn export_keying_material(
+        &self,
+        label: &[u8],
+        context: &[u8],
+        len: usize,
+    ) -> Result<Zeroizing<Vec<u8>>, ExportError> {
+        self.key_schedule.export_keying_material(label, context, len)
+    }
+
+    /// Value identifying this channel, for binding application-level authentication to it
+    pub fn channel_binding(&self) -> Vec<u8> {
+        let (client, server) = self.finished_pair();
+        let mut transcript = self.transcript_through(&server);
+        transcript.absorb(HandshakeType::Finished, &client.to_bytes());
+        compute_channel_binding(self.hash(), &transcript.snapshot())
+    }
+
+    fn hash(&self) -> HashAlgorithm {
+        self.key_schedule.hash()
+    }
+
+    pub(crate) fn exporter_master_secret(&self) -> SecretBytes {
+        self.key_schedule.exporter_master_secret().into()
+    }
+
+    /// Client and server application traffic secrets, the roots of each direction's key updates
+    pub(crate) fn application_traffic_secrets(&self) -> (SecretBytes, SecretBytes) {
+        (
+            self.key_schedule.client_application_traffic_secret().into(),
+            self.key_schedule.server_application_traffic_secret().into(),
+        )
+    }
+}
+
+/// Answer a ClientHello in the original v1 wire format, so clients deployed before version
+/// negotiation keep working. The server must permit v1 and
+/// `KYBER768_X25519_CHACHA20POLY1305_SHA256`, the only suite that format can express.
+/// Clients never send this format themselves, so a man-in-the-middle cannot downgrade a
+/// newer client to it.
+pub fn handle_legacy_client_hello(
+    client_hello: LegacyClientHello,
+    config: &HandshakeConfig,
+) -> Result<(LegacyServerHello, LegacySession), HandshakeError> {
+    handle_legacy_client_hello_with_rng(client_hello, config, &mut OsRng)
+}
+
+/// Like `handle_legacy_client_hello`, drawing the encapsulation randomness from `rng`
+pub fn handle_legacy_client_hello_with_rng<R: CryptoRng + RngCore>(
+    client_hello: LegacyClientHello,
+    config: &HandshakeConfig,
+    rng: &mut R,
+) -> Result<(LegacyServerHello, LegacySession), HandshakeError> {
+    if client_hello.version != PROTOCOL_V1 || !config.permitted_versions().contains(&PROTOCOL_V1)
+    {
+        return Err(HandshakeError::NoCommonVersion);
+    }
+    let suite = CipherSuite::KYBER768_X25519_CHACHA20POLY1305_SHA256;
+    if !permitted_suites(config.profile, &config.cipher_suites).contains(&suite) {
+        return Err(HandshakeError::NoCommonSuite);
+    }
+    if client_hello.kyber_pk.len() != Kyber768Kem::PUBLIC_KEY_SIZE {
+        return Err(HandshakeError::InvalidKeySize);
+    }
+
+    // v1 keeps the two secrets apart until the session key derivation
+    let kyber_pk = Kyber768Kem::public_key_from_bytes(&client_hello.kyber_pk)?;
+    let (kyber_ct, ss_pq) = Kyber768Kem::encapsulate_with_rng(&kyber_pk, rng)?;
+    let x25519_pk = X25519Dh::public_key_from_bytes(&client_hello.x25519_pk)
+        .ok_or(HandshakeError::InvalidKeySize)?;
+    let (server_x25519_pk, ss_classical) =
+        DhKem::<X25519Dh>::encapsulate_with_rng(&x25519_pk, rng)?;
+
+    let server_hello = LegacyServerHello {
+        kyber_ct: Kyber768Kem::ciphertext_to_bytes(&kyber_ct),
+        x25519_pk: X25519Dh::public_key_to_bytes(&server_x25519_pk)
+            .try_into()
+            .expect("X25519 public keys are 32 bytes"),
+    };
+    let transcript = compute_legacy_transcript(&client_hello, &server_hello);
+    let keys = derive_session_keys(&ss_pq, &ss_classical, &transcript);
+
+    Ok((server_hello, LegacySession { keys, transcript }))
+}
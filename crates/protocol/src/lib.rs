@@ -1,5 +1,10 @@
+mod error;
 pub mod framing;
 pub mod handshake;
 pub mod messages;
+pub mod record;
+mod rekey;
 pub mod session;
 pub mod transcript;
+
+pub use error::{Error, ErrorOrigin};
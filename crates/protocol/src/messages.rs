@@ -1,31 +1,389 @@
+use std::fmt;
+
+use crypto::kyber::{
+    Kyber1024Kem, Kyber512Kem, Kyber768Kem, MlKem1024Kem, MlKem512Kem, MlKem768Kem,
+};
+use crypto::hkdf::HashAlgorithm;
+use crypto::p256::P256Dh;
+use crypto::traits::{Dh, Kem};
+use crypto::x25519::X25519Dh;
+use crypto::x448::X448Dh;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Post-quantum KEM and parameter set, as its wire code point.
+///
+/// The set is open: the constants below name the KEMs this crate implements, and any other
+/// id can be claimed by a `handshake::NamedKem` implementation. Ids from `0xfe00` up are
+/// left for private use.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct KemAlgorithm(pub u16);
+
+impl KemAlgorithm {
+    /// Pre-standard round-3 CRYSTALS-Kyber512
+    pub const KYBER512: KemAlgorithm = KemAlgorithm(0);
+    /// Pre-standard round-3 CRYSTALS-Kyber768
+    pub const KYBER768: KemAlgorithm = KemAlgorithm(1);
+    /// Pre-standard round-3 CRYSTALS-Kyber1024
+    pub const KYBER1024: KemAlgorithm = KemAlgorithm(2);
+    /// FIPS 203 ML-KEM-512
+    pub const MLKEM512: KemAlgorithm = KemAlgorithm(3);
+    /// FIPS 203 ML-KEM-768
+    pub const MLKEM768: KemAlgorithm = KemAlgorithm(4);
+    /// FIPS 203 ML-KEM-1024
+    pub const MLKEM1024: KemAlgorithm = KemAlgorithm(5);
+
+    /// Public key size of a built-in KEM, or `None` for a code point this crate does not implement
+    pub fn public_key_size(self) -> Option<usize> {
+        match self {
+            KemAlgorithm::KYBER512 => Some(Kyber512Kem::PUBLIC_KEY_SIZE),
+            KemAlgorithm::KYBER768 => Some(Kyber768Kem::PUBLIC_KEY_SIZE),
+            KemAlgorithm::KYBER1024 => Some(Kyber1024Kem::PUBLIC_KEY_SIZE),
+            KemAlgorithm::MLKEM512 => Some(MlKem512Kem::PUBLIC_KEY_SIZE),
+            KemAlgorithm::MLKEM768 => Some(MlKem768Kem::PUBLIC_KEY_SIZE),
+            KemAlgorithm::MLKEM1024 => Some(MlKem1024Kem::PUBLIC_KEY_SIZE),
+            _ => None,
+        }
+    }
+
+    /// Ciphertext size of a built-in KEM, or `None` for a code point this crate does not implement
+    pub fn ciphertext_size(self) -> Option<usize> {
+        match self {
+            KemAlgorithm::KYBER512 => Some(Kyber512Kem::CIPHERTEXT_SIZE),
+            KemAlgorithm::KYBER768 => Some(Kyber768Kem::CIPHERTEXT_SIZE),
+            KemAlgorithm::KYBER1024 => Some(Kyber1024Kem::CIPHERTEXT_SIZE),
+            KemAlgorithm::MLKEM512 => Some(MlKem512Kem::CIPHERTEXT_SIZE),
+            KemAlgorithm::MLKEM768 => Some(MlKem768Kem::CIPHERTEXT_SIZE),
+            KemAlgorithm::MLKEM1024 => Some(MlKem1024Kem::CIPHERTEXT_SIZE),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for KemAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            KemAlgorithm::KYBER512 => f.write_str("Kyber512"),
+            KemAlgorithm::KYBER768 => f.write_str("Kyber768"),
+            KemAlgorithm::KYBER1024 => f.write_str("Kyber1024"),
+            KemAlgorithm::MLKEM512 => f.write_str("MlKem512"),
+            KemAlgorithm::MLKEM768 => f.write_str("MlKem768"),
+            KemAlgorithm::MLKEM1024 => f.write_str("MlKem1024"),
+            KemAlgorithm(id) => write!(f, "KemAlgorithm({id:#06x})"),
+        }
+    }
+}
+
+/// Classical Diffie-Hellman group combined with the KEM, as its wire code point. Open like
+/// `KemAlgorithm`: other ids can be claimed by a `handshake::NamedGroup` implementation.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ClassicalGroup(pub u16);
+
+impl ClassicalGroup {
+    pub const X25519: ClassicalGroup = ClassicalGroup(0);
+    /// NIST P-256, uncompressed points
+    pub const P256: ClassicalGroup = ClassicalGroup(1);
+    pub const X448: ClassicalGroup = ClassicalGroup(2);
+
+    /// Public key size of a built-in group, or `None` for a code point this crate does not implement
+    pub fn public_key_size(self) -> Option<usize> {
+        match self {
+            ClassicalGroup::X25519 => Some(X25519Dh::PUBLIC_KEY_SIZE),
+            ClassicalGroup::P256 => Some(P256Dh::PUBLIC_KEY_SIZE),
+            ClassicalGroup::X448 => Some(X448Dh::PUBLIC_KEY_SIZE),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for ClassicalGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ClassicalGroup::X25519 => f.write_str("X25519"),
+            ClassicalGroup::P256 => f.write_str("P256"),
+            ClassicalGroup::X448 => f.write_str("X448"),
+            ClassicalGroup(id) => write!(f, "ClassicalGroup({id:#06x})"),
+        }
+    }
+}
+
+/// AEAD protecting application data once the handshake completes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AeadAlgorithm {
+    ChaCha20Poly1305,
+    Aes256Gcm,
+}
+
+/// Hash underlying HKDF and the handshake transcript
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KdfHash {
+    Sha256,
+    Sha384,
+}
+
+impl KdfHash {
+    pub fn hash_algorithm(self) -> HashAlgorithm {
+        match self {
+            KdfHash::Sha256 => HashAlgorithm::Sha256,
+            KdfHash::Sha384 => HashAlgorithm::Sha384,
+        }
+    }
+}
+
+/// One combination of primitives the channel can run with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CipherSuite {
+    pub kem: KemAlgorithm,
+    pub group: ClassicalGroup,
+    pub aead: AeadAlgorithm,
+    pub kdf: KdfHash,
+}
+
+impl CipherSuite {
+    pub const MLKEM768_X25519_CHACHA20POLY1305_SHA256: CipherSuite = CipherSuite {
+        kem: KemAlgorithm::MLKEM768,
+        group: ClassicalGroup::X25519,
+        aead: AeadAlgorithm::ChaCha20Poly1305,
+        kdf: KdfHash::Sha256,
+    };
+
+    pub const MLKEM768_X25519_AES256GCM_SHA256: CipherSuite = CipherSuite {
+        kem: KemAlgorithm::MLKEM768,
+        group: ClassicalGroup::X25519,
+        aead: AeadAlgorithm::Aes256Gcm,
+        kdf: KdfHash::Sha256,
+    };
+
+    pub const KYBER768_X25519_CHACHA20POLY1305_SHA256: CipherSuite = CipherSuite {
+        kem: KemAlgorithm::KYBER768,
+        group: ClassicalGroup::X25519,
+        aead: AeadAlgorithm::ChaCha20Poly1305,
+        kdf: KdfHash::Sha256,
+    };
+
+    pub const MLKEM768_P256_AES256GCM_SHA256: CipherSuite = CipherSuite {
+        kem: KemAlgorithm::MLKEM768,
+        group: ClassicalGroup::P256,
+        aead: AeadAlgorithm::Aes256Gcm,
+        kdf: KdfHash::Sha256,
+    };
+
+    pub const MLKEM768_X448_CHACHA20POLY1305_SHA256: CipherSuite = CipherSuite {
+        kem: KemAlgorithm::MLKEM768,
+        group: ClassicalGroup::X448,
+        aead: AeadAlgorithm::ChaCha20Poly1305,
+        kdf: KdfHash::Sha256,
+    };
+
+    pub const MLKEM1024_X448_AES256GCM_SHA384: CipherSuite = CipherSuite {
+        kem: KemAlgorithm::MLKEM1024,
+        group: ClassicalGroup::X448,
+        aead: AeadAlgorithm::Aes256Gcm,
+        kdf: KdfHash::Sha384,
+    };
+
+    pub const MLKEM1024_X448_CHACHA20POLY1305_SHA384: CipherSuite = CipherSuite {
+        kem: KemAlgorithm::MLKEM1024,
+        group: ClassicalGroup::X448,
+        aead: AeadAlgorithm::ChaCha20Poly1305,
+        kdf: KdfHash::Sha384,
+    };
+}
+
+/// A client KEM public key for one of the offered KEMs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KemShare {
+    pub kem: KemAlgorithm,
+    pub public_key: Vec<u8>,
+}
+
+/// A client public key in one of the offered classical groups
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DhShare {
+    pub group: ClassicalGroup,
+    pub public_key: Vec<u8>,
+}
+
+/// An opaque type-length-value entry in a hello's extensions block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Extension {
+    pub extension_type: u16,
+    pub data: Vec<u8>,
+}
+
+/// A known extension with a typed body.
+///
+/// Implementing this trait is how an extension is registered: its `TYPE` claims an
+/// id, and the body is postcard-encoded into `Extension::data`. Peers that do not
+/// know the id skip the entry, but it still feeds the transcript.
+pub trait HelloExtension: Serialize + DeserializeOwned {
+    const TYPE: u16;
+
+    fn to_extension(&self) -> Extension {
+        Extension {
+            extension_type: Self::TYPE,
+            data: postcard::to_allocvec(self).expect("serialization should not fail"),
+        }
+    }
+
+    fn from_extension(extension: &Extension) -> Result<Self, MessageError> {
+        postcard::from_bytes(&extension.data)
+            .map_err(|error| MessageError::InvalidExtension(extension.extension_type, error))
+    }
+}
+
+/// Reject extension blocks that carry the same type more than once
+pub fn check_extensions(extensions: &[Extension]) -> Result<(), MessageError> {
+    for (i, extension) in extensions.iter().enumerate() {
+        if extensions[..i]
+            .iter()
+            .any(|earlier| earlier.extension_type == extension.extension_type)
+        {
+            return Err(MessageError::DuplicateExtension(extension.extension_type));
+        }
+    }
+    Ok(())
+}
+
+/// Decode extension `E` if present, skipping every other (possibly unknown) entry
+pub fn find_extension<E: HelloExtension>(
+    extensions: &[Extension],
+) -> Result<Option<E>, MessageError> {
+    extensions
+        .iter()
+        .find(|extension| extension.extension_type == E::TYPE)
+        .map(E::from_extension)
+        .transpose()
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClientHello {
+    /// Supported protocol versions, most preferred first
+    pub versions: Vec<u8>,
+    /// Offered suites, most preferred first
+    pub cipher_suites: Vec<CipherSuite>,
+    /// One share per distinct KEM among `cipher_suites`
+    pub kem_shares: Vec<KemShare>,
+    /// One share per distinct classical group among `cipher_suites`
+    pub dh_shares: Vec<DhShare>,
+    pub extensions: Vec<Extension>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerHello {
+    pub version: u8,
+    pub cipher_suite: CipherSuite,
+    pub kyber_ct: Vec<u8>,
+    /// Ephemeral public key, the classical half of the hybrid ciphertext
+    pub dh_public_key: Vec<u8>,
+    pub extensions: Vec<Extension>,
+}
+
+/// The ClientHello of the original v1 wire format, from before version negotiation: one
+/// Kyber768 key and one X25519 key, sent as a bare frame rather than in a record. Its first
+/// byte is the version, 1, which no `ContentType` uses, so a server tells the two apart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegacyClientHello {
     pub version: u8,
     pub kyber_pk: Vec<u8>,
     pub x25519_pk: [u8; 32],
 }
 
+/// The ServerHello of the original v1 wire format, answering a `LegacyClientHello`
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ServerHello {
+pub struct LegacyServerHello {
     pub kyber_ct: Vec<u8>,
     pub x25519_pk: [u8; 32],
 }
 
+/// An application data frame of the original v1 wire format
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AppData {
+pub struct LegacyAppData {
     pub seq: u64,
     pub ciphertext: Vec<u8>,
 }
 
+/// Key confirmation: HMAC over the transcript under the sender's Finished key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finished {
+    /// One hash long under the suite's KDF hash
+    pub verify_data: Vec<u8>,
+}
+
+/// Why a connection is being closed; codes follow the TLS alert registry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "u8", try_from = "u8")]
+#[repr(u8)]
+pub enum AlertDescription {
+    /// Orderly shutdown: the sender will send nothing more
+    CloseNotify = 0,
+    UnexpectedMessage = 10,
+    BadRecordMac = 20,
+    HandshakeFailure = 40,
+    IllegalParameter = 47,
+    DecodeError = 50,
+    /// A Finished MAC did not verify
+    DecryptError = 51,
+    ProtocolVersion = 70,
+    InternalError = 80,
+}
+
+impl From<AlertDescription> for u8 {
+    fn from(description: AlertDescription) -> Self {
+        description as u8
+    }
+}
+
+impl TryFrom<u8> for AlertDescription {
+    type Error = UnknownAlert;
+
+    fn try_from(code: u8) -> Result<Self, UnknownAlert> {
+        Ok(match code {
+            0 => AlertDescription::CloseNotify,
+            10 => AlertDescription::UnexpectedMessage,
+            20 => AlertDescription::BadRecordMac,
+            40 => AlertDescription::HandshakeFailure,
+            47 => AlertDescription::IllegalParameter,
+            50 => AlertDescription::DecodeError,
+            51 => AlertDescription::DecryptError,
+            70 => AlertDescription::ProtocolVersion,
+            80 => AlertDescription::InternalError,
+            _ => return Err(UnknownAlert(code)),
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("unknown alert code {0}")]
+pub struct UnknownAlert(pub u8);
+
+/// Sent in the clear when the handshake fails and encrypted as a record once the channel
+/// is up; every alert but `close_notify` ends the connection with an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alert {
+    pub description: AlertDescription,
+}
+
 impl ClientHello {
+    pub fn kem_share(&self, kem: KemAlgorithm) -> Option<&KemShare> {
+        self.kem_shares.iter().find(|share| share.kem == kem)
+    }
+
+    pub fn dh_share(&self, group: ClassicalGroup) -> Option<&DhShare> {
+        self.dh_shares.iter().find(|share| share.group == group)
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         postcard::to_allocvec(self).expect("serialization should not fail")
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, MessageError> {
-        postcard::from_bytes(bytes).map_err(|_| MessageError::InvalidFormat)
+        let hello: Self = postcard::from_bytes(bytes).map_err(MessageError::InvalidFormat)?;
+        check_extensions(&hello.extensions)?;
+        Ok(hello)
     }
 }
 
@@ -35,21 +393,84 @@ impl ServerHello {
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, MessageError> {
-        postcard::from_bytes(bytes).map_err(|_| MessageError::InvalidFormat)
+        let hello: Self = postcard::from_bytes(bytes).map_err(MessageError::InvalidFormat)?;
+        check_extensions(&hello.extensions)?;
+        Ok(hello)
+    }
+}
+
+impl Finished {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        postcard::to_allocvec(self).expect("serialization should not fail")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MessageError> {
+        postcard::from_bytes(bytes).map_err(MessageError::InvalidFormat)
+    }
+}
+
+impl LegacyClientHello {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        postcard::to_allocvec(self).expect("serialization should not fail")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MessageError> {
+        postcard::from_bytes(bytes).map_err(MessageError::InvalidFormat)
+    }
+}
+
+impl LegacyServerHello {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        postcard::to_allocvec(self).expect("serialization should not fail")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MessageError> {
+        postcard::from_bytes(bytes).map_err(MessageError::InvalidFormat)
     }
 }
 
-impl AppData {
+impl LegacyAppData {
     pub fn to_bytes(&self) -> Vec<u8> {
         postcard::to_allocvec(self).expect("serialization should not fail")
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, MessageError> {
-        postcard::from_bytes(bytes).map_err(|_| MessageError::InvalidFormat)
+        postcard::from_bytes(bytes).map_err(MessageError::InvalidFormat)
     }
 }
 
-#[derive(Debug)]
+impl Alert {
+    pub fn new(description: AlertDescription) -> Self {
+        Self { description }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        postcard::to_allocvec(self).expect("serialization should not fail")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MessageError> {
+        postcard::from_bytes(bytes).map_err(MessageError::InvalidFormat)
+    }
+}
+
+#[derive(Debug, Error)]
 pub enum MessageError {
-    InvalidFormat,
+    #[error("malformed message")]
+    InvalidFormat(#[source] postcard::Error),
+    #[error("extension {0:#06x} appears more than once")]
+    DuplicateExtension(u16),
+    #[error("extension {0:#06x} has a malformed body")]
+    InvalidExtension(u16, #[source] postcard::Error),
+}
+
+impl MessageError {
+    /// The alert that tells the peer why its message was rejected
+    pub fn alert(&self) -> AlertDescription {
+        match self {
+            MessageError::InvalidFormat(_) | MessageError::InvalidExtension(..) => {
+                AlertDescription::DecodeError
+            }
+            MessageError::DuplicateExtension(_) => AlertDescription::IllegalParameter,
+        }
+    }
 }
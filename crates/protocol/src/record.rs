@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::handshake::HandshakeError;
+use crate::messages::{Alert, MessageError};
+
+/// What a record carries. Encrypted records authenticate it in the AEAD's additional data,
+/// so a man-in-the-middle cannot turn a data record into a control record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "u8", try_from = "u8")]
+#[repr(u8)]
+pub enum ContentType {
+    Alert = 21,
+    /// ClientHello, ServerHello and Finished, sent in the clear
+    Handshake = 22,
+    ApplicationData = 23,
+    /// Channel maintenance: key updates and the hybrid re-key exchange
+    Control = 24,
+}
+
+impl From<ContentType> for u8 {
+    fn from(content_type: ContentType) -> Self {
+        content_type as u8
+    }
+}
+
+impl TryFrom<u8> for ContentType {
+    type Error = UnknownContentType;
+
+    fn try_from(code: u8) -> Result<Self, UnknownContentType> {
+        Ok(match code {
+            21 => ContentType::Alert,
+            22 => ContentType::Handshake,
+            23 => ContentType::ApplicationData,
+            24 => ContentType::Control,
+            _ => return Err(UnknownContentType(code)),
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("unknown content type {0}")]
+pub struct UnknownContentType(pub u8);
+
+/// Control messages, carried as the first payload byte of a `Control` record
+pub(crate) const CONTROL_KEY_UPDATE: u8 = 1;
+pub(crate) const CONTROL_REKEY_REQUEST: u8 = 2;
+pub(crate) const CONTROL_REKEY_RESPONSE: u8 = 3;
+pub(crate) const CONTROL_REKEY_FINISHED: u8 = 4;
+
+/// One unit on the wire: a content type, an optional sequence number and a length-prefixed
+/// payload, which is AEAD ciphertext once the channel is up
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    pub content_type: ContentType,
+    /// Present only when the channel uses explicit sequence numbers; over a reliable,
+    /// ordered transport both sides count records instead
+    pub seq: Option<u64>,
+    pub payload: Vec<u8>,
+}
+
+impl Record {
+    /// A record sent in the clear, before the channel has keys
+    pub fn plaintext(content_type: ContentType, payload: Vec<u8>) -> Self {
+        Self {
+            content_type,
+            seq: None,
+            payload,
+        }
+    }
+
+    pub fn handshake(message: Vec<u8>) -> Self {
+        Self::plaintext(ContentType::Handshake, message)
+    }
+
+    pub fn alert(alert: Alert) -> Self {
+        Self::plaintext(ContentType::Alert, alert.to_bytes())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        postcard::to_allocvec(self).expect("serialization should not fail")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MessageError> {
+        postcard::from_bytes(bytes).map_err(MessageError::InvalidFormat)
+    }
+}
+
+/// Route a plaintext record received during the handshake: the body of a handshake message,
+/// or the error reported by the peer's alert
+pub fn handshake_message(record: Record) -> Result<Vec<u8>, HandshakeError> {
+    match record.content_type {
+        ContentType::Handshake if record.seq.is_none() => Ok(record.payload),
+        ContentType::Alert => {
+            let alert = Alert::from_bytes(&record.payload).map_err(HandshakeError::InvalidMessage)?;
+            Err(HandshakeError::AlertReceived(alert.description))
+        }
+        _ => Err(HandshakeError::UnexpectedMessage),
+    }
+}
@@ -0,0 +1,107 @@
+use crypto::secret::SecretBytes;
+use rand::{CryptoRng, RngCore};
+
+use crate::handshake::{
+    dh_keypair, hybrid_decapsulate, hybrid_encapsulate, is_builtin, kem_keypair, HybridKeys,
+};
+use crate::messages::CipherSuite;
+
+/// Initiator state between sending a re-key request and receiving the response
+pub(crate) struct PendingRekey {
+    keys: HybridKeys,
+    request: Vec<u8>,
+}
+
+/// Fresh hybrid shared secret from one re-key exchange, bound to the exchanged messages
+pub(crate) struct RekeySecret {
+    pub shared_secret: SecretBytes,
+    pub transcript: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub(crate) struct RekeyError;
+
+fn rekey_transcript(suite: CipherSuite, request: &[u8], response: &[u8]) -> Vec<u8> {
+    let hash = suite.kdf.hash_algorithm();
+    hash.digest(&[b"hybrid-pq-channel rekey", request, response].concat())
+}
+
+/// Whether the channel can re-key over `suite`: the exchange runs the built-in primitives,
+/// so sessions negotiated over caller-supplied ones cannot
+pub(crate) fn supported(suite: CipherSuite) -> bool {
+    is_builtin(&suite)
+}
+
+/// Build a re-key request body: a fresh KEM public key followed by a fresh public key in
+/// the session's classical group
+pub(crate) fn start<R: CryptoRng + RngCore>(
+    suite: CipherSuite,
+    rng: &mut R,
+) -> (Vec<u8>, PendingRekey) {
+    let (kem_pk, kem_sk) = kem_keypair(suite.kem, rng);
+    let (dh_pk, dh_sk) = dh_keypair(suite.group, rng);
+
+    let mut request = kem_pk.clone();
+    request.extend_from_slice(&dh_pk);
+
+    let pending = PendingRekey {
+        keys: HybridKeys {
+            kem_pk,
+            kem_sk,
+            dh_pk,
+            dh_sk,
+        },
+        request: request.clone(),
+    };
+    (request, pending)
+}
+
+/// Answer a re-key request body with the KEM ciphertext and classical encapsulation
+pub(crate) fn respond<R: CryptoRng + RngCore>(
+    suite: CipherSuite,
+    request: &[u8],
+    rng: &mut R,
+) -> Result<(Vec<u8>, RekeySecret), RekeyError> {
+    let kem_pk_size = suite.kem.public_key_size().ok_or(RekeyError)?;
+    if request.len() < kem_pk_size {
+        return Err(RekeyError);
+    }
+    let (kem_pk, dh_pk) = request.split_at(kem_pk_size);
+
+    let (kem_ct, dh_ct, shared_secret) =
+        hybrid_encapsulate(suite, kem_pk, dh_pk, rng).map_err(|_| RekeyError)?;
+
+    let mut response = kem_ct;
+    response.extend_from_slice(&dh_ct);
+
+    let transcript = rekey_transcript(suite, request, &response);
+    Ok((
+        response,
+        RekeySecret {
+            shared_secret,
+            transcript,
+        },
+    ))
+}
+
+/// Complete the initiator's side of the exchange
+pub(crate) fn finish(
+    suite: CipherSuite,
+    pending: PendingRekey,
+    response: &[u8],
+) -> Result<RekeySecret, RekeyError> {
+    let kem_ct_size = suite.kem.ciphertext_size().ok_or(RekeyError)?;
+    if response.len() < kem_ct_size {
+        return Err(RekeyError);
+    }
+    let (kem_ct, dh_ct) = response.split_at(kem_ct_size);
+
+    let shared_secret =
+        hybrid_decapsulate(suite, &pending.keys, kem_ct, dh_ct).map_err(|_| RekeyError)?;
+
+    let transcript = rekey_transcript(suite, &pending.request, response);
+    Ok(RekeySecret {
+        shared_secret,
+        transcript,
+    })
+}
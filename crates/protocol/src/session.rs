@@ -1,77 +1,584 @@
-use crypto::aead;
-use crypto::hkdf::SessionKeys;
+use std::fmt;
 
-use crate::messages::AppData;
+use crypto::aead::{AeadError, Aes256GcmAead, ChaCha20Poly1305Aead, TAG_SIZE};
+use crypto::hkdf::{
+    export_keying_material, next_traffic_secret, rekey_traffic_secret, traffic_key_iv,
+    ExportError, HashAlgorithm, SessionKeys,
+};
+use crypto::secret::SecretBytes;
+use crypto::traits::Aead;
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
+use thiserror::Error;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::handshake::{HandshakeError, LegacySession, Session};
+use crate::messages::{AeadAlgorithm, Alert, AlertDescription, CipherSuite, LegacyAppData};
+use crate::record::{
+    ContentType, Record, CONTROL_KEY_UPDATE, CONTROL_REKEY_FINISHED, CONTROL_REKEY_REQUEST,
+    CONTROL_REKEY_RESPONSE,
+};
+use crate::rekey::{self, PendingRekey, RekeySecret};
+
+/// How a channel numbers its records and when it ratchets its sending keys without being asked to
+#[derive(Debug, Clone)]
+pub struct ChannelConfig {
+    /// Send a KeyUpdate after this many records under one key
+    pub key_update_after_records: Option<u64>,
+    /// Send a KeyUpdate after this many plaintext bytes under one key
+    pub key_update_after_bytes: Option<u64>,
+    /// Start a fresh hybrid re-key after sending this many records since the last one
+    pub rekey_after_records: Option<u64>,
+    /// Carry each record's sequence number on the wire and accept gaps, for transports that
+    /// may drop frames. Over a reliable, ordered stream both sides count records instead.
+    pub explicit_sequence_numbers: bool,
+}
+
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        Self {
+            key_update_after_records: Some(1 << 20),
+            key_update_after_bytes: Some(1 << 30),
+            rekey_after_records: Some(1 << 16),
+            explicit_sequence_numbers: false,
+        }
+    }
+}
+
+/// Randomness a channel keeps for the in-band re-key
+pub trait ChannelRng: CryptoRng + RngCore + Send {}
+
+impl<R: CryptoRng + RngCore + Send> ChannelRng for R {}
+
+/// One direction's traffic secret and the AEAD key material expanded from it
+struct DirectionKeys {
+    hash: HashAlgorithm,
+    traffic_secret: SecretBytes,
+    key: [u8; 32],
+    nonce_base: [u8; 12],
+    records: u64,
+    bytes: u64,
+}
+
+impl DirectionKeys {
+    fn new(
+        hash: HashAlgorithm,
+        traffic_secret: SecretBytes,
+        key: [u8; 32],
+        nonce_base: [u8; 12],
+    ) -> Self {
+        Self {
+            hash,
+            traffic_secret,
+            key,
+            nonce_base,
+            records: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Move to the next traffic secret and wipe the old one and its keys
+    fn ratchet(&mut self) {
+        let next = next_traffic_secret(self.hash, &self.traffic_secret);
+        self.replace_secret(next);
+    }
+
+    /// Mix a re-key exchange into the current traffic secret
+    fn rekey(&mut self, secret: &RekeySecret) {
+        let next = rekey_traffic_secret(
+            self.hash,
+            &self.traffic_secret,
+            &secret.shared_secret,
+            &secret.transcript,
+        );
+        self.replace_secret(next);
+    }
+
+    fn replace_secret(&mut self, next: SecretBytes) {
+        self.key.zeroize();
+        self.nonce_base.zeroize();
+
+        let (key, nonce_base) = traffic_key_iv(self.hash, &next);
+        self.traffic_secret = next;
+        self.key = *key;
+        self.nonce_base = *nonce_base;
+        self.records = 0;
+        self.bytes = 0;
+    }
+}
+
+impl Drop for DirectionKeys {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.nonce_base.zeroize();
+    }
+}
+
+/// A record received from the peer
+#[derive(Debug, PartialEq, Eq)]
+pub enum Incoming {
+    Data(Vec<u8>),
+    /// The peer ratcheted its sending keys; later records from it use the new keys
+    KeyUpdate,
+    /// A step of a hybrid re-key was processed; `reply` must be sent to the peer in order
+    Rekey { reply: Vec<Record> },
+    /// The peer will send nothing more; the stream may now end cleanly
+    CloseNotify,
+}
+
+/// Progress of an in-band hybrid re-key
+enum RekeyState {
+    Idle,
+    /// We sent a request and keep both directions on the old keys until the response
+    AwaitingResponse(PendingRekey),
+    /// We responded and switched our sending keys; the peer's switch is still to come
+    AwaitingFinished(RekeySecret),
+}
 
 pub struct SecureChannel {
-    keys: SessionKeys,
-    transcript: [u8; 32],
+    send: DirectionKeys,
+    recv: DirectionKeys,
+    transcript: Vec<u8>,
+    aead: AeadAlgorithm,
+    cipher_suite: CipherSuite,
     is_client: bool,
+    config: ChannelConfig,
+    rekey_state: RekeyState,
+    records_since_rekey: u64,
+    /// The alert that ended the peer's side, if any
+    peer_alert: Option<AlertDescription>,
+    exporter_master: SecretBytes,
+    channel_binding: Vec<u8>,
     send_seq: u64,
     recv_seq: u64,
+    /// Draws the key shares and encapsulations of every re-key, in either role
+    rng: Box<dyn ChannelRng>,
 }
 
-#[derive(Debug)]
+impl fmt::Debug for SecureChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecureChannel")
+            .field("cipher_suite", &self.cipher_suite)
+            .field("is_client", &self.is_client)
+            .field("send_seq", &self.send_seq)
+            .field("recv_seq", &self.recv_seq)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Error)]
 pub enum ChannelError {
-    DecryptionFailed,
+    #[error("record failed to decrypt")]
+    DecryptionFailed(#[from] AeadError),
+    #[error("record sequence number was already seen")]
     ReplayDetected,
+    /// A record carried a sequence number when they are implicit, or lacked one when explicit
+    #[error("record sequence number does not match the channel's numbering")]
     InvalidSequence,
+    /// A record decrypted but its inner type or body was malformed, or arrived out of turn
+    #[error("malformed or unexpected record")]
+    InvalidRecord,
+    /// A re-key was requested while another one is still in flight
+    #[error("a re-key is already in progress")]
+    RekeyInProgress,
+    /// The session runs caller-supplied primitives, and the in-band re-key only runs the
+    /// built-in ones
+    #[error("the session's primitives cannot re-key in band")]
+    RekeyUnsupported,
+    /// The peer ended the connection with an error alert
+    #[error("the peer sent alert {0:?}")]
+    AlertReceived(AlertDescription),
+    /// The stream ended without a close_notify, so the data may have been cut short
+    #[error("connection closed without close_notify")]
+    Truncated,
+    /// A record arrived after the peer's alert ended its side
+    #[error("record received after the peer closed the channel")]
+    Closed,
 }
 
-fn build_aad(seq: u64, transcript: &[u8; 32]) -> Vec<u8> {
-    let mut aad = Vec::with_capacity(8 + 32);
+impl ChannelError {
+    /// The alert to send before dropping the connection, or `None` when the channel is
+    /// still usable or the peer has already ended it
+    pub fn alert(&self) -> Option<AlertDescription> {
+        match self {
+            ChannelError::DecryptionFailed(_) => Some(AlertDescription::BadRecordMac),
+            ChannelError::InvalidSequence | ChannelError::InvalidRecord => {
+                Some(AlertDescription::UnexpectedMessage)
+            }
+            ChannelError::ReplayDetected
+            | ChannelError::RekeyInProgress
+            | ChannelError::RekeyUnsupported
+            | ChannelError::AlertReceived(_)
+            | ChannelError::Truncated
+            | ChannelError::Closed => None,
+        }
+    }
+}
+
+/// Additional data for a record: its sequence number, content type and payload length, and
+/// the handshake it belongs to
+fn build_aad(seq: u64, content_type: ContentType, length: usize, transcript: &[u8]) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(8 + 1 + 8 + transcript.len());
     aad.extend_from_slice(&seq.to_be_bytes());
+    aad.push(content_type.into());
+    aad.extend_from_slice(&(length as u64).to_be_bytes());
     aad.extend_from_slice(transcript);
     aad
 }
 
 impl SecureChannel {
-    pub fn new(keys: SessionKeys, transcript: [u8; 32], is_client: bool) -> Self {
-        Self {
-            keys,
-            transcript,
-            is_client,
+    /// Open a channel over a session whose peer Finished has been verified
+    pub fn new(session: Session) -> Result<Self, HandshakeError> {
+        Self::with_config(session, ChannelConfig::default())
+    }
+
+    pub fn with_config(session: Session, config: ChannelConfig) -> Result<Self, HandshakeError> {
+        Self::with_rng(session, config, OsRng)
+    }
+
+    /// Like `with_config`, drawing re-key randomness from `rng`. With a seeded RNG on both
+    /// sides and a seeded handshake, re-keyed sessions are reproducible.
+    pub fn with_rng(
+        session: Session,
+        config: ChannelConfig,
+        rng: impl ChannelRng + 'static,
+    ) -> Result<Self, HandshakeError> {
+        if !session.is_confirmed() {
+            return Err(HandshakeError::FinishedNotVerified);
+        }
+        let exporter_master = session.exporter_master_secret();
+        let channel_binding = session.channel_binding();
+        let (client_secret, server_secret) = session.application_traffic_secrets();
+        let SessionKeys {
+            k_client_to_server,
+            k_server_to_client,
+            nonce_base_c2s,
+            nonce_base_s2c,
+        } = session.keys;
+
+        let hash = session.cipher_suite.kdf.hash_algorithm();
+        let c2s = DirectionKeys::new(hash, client_secret, k_client_to_server, nonce_base_c2s);
+        let s2c = DirectionKeys::new(hash, server_secret, k_server_to_client, nonce_base_s2c);
+        let (send, recv) = if session.is_client { (c2s, s2c) } else { (s2c, c2s) };
+
+        Ok(Self {
+            send,
+            recv,
+            transcript: session.transcript,
+            aead: session.cipher_suite.aead,
+            cipher_suite: session.cipher_suite,
+            is_client: session.is_client,
+            config,
+            rekey_state: RekeyState::Idle,
+            records_since_rekey: 0,
+            peer_alert: None,
+            exporter_master,
+            channel_binding,
             send_seq: 0,
             recv_seq: 0,
+            rng: Box::new(rng),
+        })
+    }
+
+    /// Encrypt application data. Returns the record to send, followed by a KeyUpdate or
+    /// re-key request when the configured limits are reached.
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> Vec<Record> {
+        let mut records = vec![self.seal(ContentType::ApplicationData, plaintext)];
+        self.send.bytes += plaintext.len() as u64;
+
+        let records_due = self
+            .config
+            .key_update_after_records
+            .is_some_and(|limit| self.send.records >= limit);
+        let bytes_due = self
+            .config
+            .key_update_after_bytes
+            .is_some_and(|limit| self.send.bytes >= limit);
+        if records_due || bytes_due {
+            records.push(self.update_keys());
+        }
+
+        let rekey_due = matches!(self.rekey_state, RekeyState::Idle)
+            && rekey::supported(self.cipher_suite)
+            && self
+                .config
+                .rekey_after_records
+                .is_some_and(|limit| self.records_since_rekey >= limit);
+        if rekey_due {
+            records.push(self.rekey().expect("no re-key in flight"));
+        }
+        records
+    }
+
+    /// Start a fresh hybrid KEM + DH exchange with the peer in the session's group, carried in encrypted records.
+    /// Both directions switch to keys mixing in its output once the exchange completes.
+    pub fn rekey(&mut self) -> Result<Record, ChannelError> {
+        if !matches!(self.rekey_state, RekeyState::Idle) {
+            return Err(ChannelError::RekeyInProgress);
         }
+        if !rekey::supported(self.cipher_suite) {
+            return Err(ChannelError::RekeyUnsupported);
+        }
+        let (request, pending) = rekey::start(self.cipher_suite, &mut self.rng);
+        self.rekey_state = RekeyState::AwaitingResponse(pending);
+        self.records_since_rekey = 0;
+        Ok(self.seal_control(CONTROL_REKEY_REQUEST, &request))
+    }
+
+    /// Ratchet the sending keys now. The returned KeyUpdate is the last record under the old keys.
+    pub fn update_keys(&mut self) -> Record {
+        let record = self.seal_control(CONTROL_KEY_UPDATE, &[]);
+        self.send.ratchet();
+        record
+    }
+
+    /// Close our sending side. The returned close_notify is the last record to send.
+    pub fn close(&mut self) -> Record {
+        self.alert(AlertDescription::CloseNotify)
+    }
+
+    /// An alert record telling the peer why the connection is being dropped, such as
+    /// `ChannelError::alert` for a record that failed
+    pub fn alert(&mut self, description: AlertDescription) -> Record {
+        self.seal(ContentType::Alert, &Alert::new(description).to_bytes())
     }
 
-    pub fn encrypt(&mut self, plaintext: &[u8]) -> AppData {
+    /// Report the transport's end of stream: clean only once the peer's close_notify arrived
+    pub fn end_of_stream(&self) -> Result<(), ChannelError> {
+        match self.peer_alert {
+            Some(AlertDescription::CloseNotify) => Ok(()),
+            Some(description) => Err(ChannelError::AlertReceived(description)),
+            None => Err(ChannelError::Truncated),
+        }
+    }
+
+    /// Authenticate a record and hand it to the handler for its content type
+    pub fn decrypt(&mut self, record: &Record) -> Result<Incoming, ChannelError> {
+        if self.peer_alert.is_some() {
+            return Err(ChannelError::Closed);
+        }
+        // Handshake messages never travel inside the channel
+        if record.content_type == ContentType::Handshake {
+            return Err(ChannelError::InvalidRecord);
+        }
+        let seq = match (record.seq, self.config.explicit_sequence_numbers) {
+            (Some(seq), true) if seq <= self.recv_seq => return Err(ChannelError::ReplayDetected),
+            (Some(seq), true) => seq,
+            // A replayed, dropped or reordered record fails to authenticate under the next number
+            (None, false) => self.recv_seq + 1,
+            _ => return Err(ChannelError::InvalidSequence),
+        };
+
+        let key = &self.recv.key;
+        let nonce_base = &self.recv.nonce_base;
+        let payload = &record.payload;
+        let aad = build_aad(seq, record.content_type, payload.len(), &self.transcript);
+        let plaintext = match self.aead {
+            AeadAlgorithm::ChaCha20Poly1305 => {
+                ChaCha20Poly1305Aead::decrypt(key, nonce_base, seq, &aad, payload)
+            }
+            AeadAlgorithm::Aes256Gcm => Aes256GcmAead::decrypt(key, nonce_base, seq, &aad, payload),
+        }?;
+
+        match record.content_type {
+            ContentType::ApplicationData => {
+                self.recv_seq = seq;
+                Ok(Incoming::Data(plaintext))
+            }
+            ContentType::Control => self.handle_control(seq, &plaintext),
+            ContentType::Alert => self.handle_alert(seq, &plaintext),
+            ContentType::Handshake => unreachable!("rejected before decryption"),
+        }
+    }
+
+    fn handle_control(&mut self, seq: u64, plaintext: &[u8]) -> Result<Incoming, ChannelError> {
+        let (&control_type, body) = plaintext.split_first().ok_or(ChannelError::InvalidRecord)?;
+        match control_type {
+            CONTROL_KEY_UPDATE if body.is_empty() => {
+                self.recv_seq = seq;
+                self.recv.ratchet();
+                Ok(Incoming::KeyUpdate)
+            }
+            CONTROL_REKEY_REQUEST | CONTROL_REKEY_RESPONSE | CONTROL_REKEY_FINISHED => {
+                self.handle_rekey(seq, control_type, body)
+            }
+            _ => Err(ChannelError::InvalidRecord),
+        }
+    }
+
+    fn handle_alert(&mut self, seq: u64, plaintext: &[u8]) -> Result<Incoming, ChannelError> {
+        let alert = Alert::from_bytes(plaintext).map_err(|_| ChannelError::InvalidRecord)?;
+        self.recv_seq = seq;
+        self.peer_alert = Some(alert.description);
+        if alert.description != AlertDescription::CloseNotify {
+            return Err(ChannelError::AlertReceived(alert.description));
+        }
+        Ok(Incoming::CloseNotify)
+    }
+
+    fn handle_rekey(
+        &mut self,
+        seq: u64,
+        control_type: u8,
+        body: &[u8],
+    ) -> Result<Incoming, ChannelError> {
+        let state = std::mem::replace(&mut self.rekey_state, RekeyState::Idle);
+        let reply = match (control_type, state) {
+            // Both sides started at once: the client's request wins and the server's is dropped
+            (CONTROL_REKEY_REQUEST, state @ RekeyState::AwaitingResponse(_)) if self.is_client => {
+                self.rekey_state = state;
+                Vec::new()
+            }
+            (CONTROL_REKEY_REQUEST, RekeyState::Idle | RekeyState::AwaitingResponse(_)) => {
+                let (response, secret) = rekey::respond(self.cipher_suite, body, &mut self.rng)
+                    .map_err(|_| ChannelError::InvalidRecord)?;
+                let record = self.seal_control(CONTROL_REKEY_RESPONSE, &response);
+                self.send.rekey(&secret);
+                self.records_since_rekey = 0;
+                self.rekey_state = RekeyState::AwaitingFinished(secret);
+                vec![record]
+            }
+            (CONTROL_REKEY_RESPONSE, RekeyState::AwaitingResponse(pending)) => {
+                let secret = rekey::finish(self.cipher_suite, pending, body)
+                    .map_err(|_| ChannelError::InvalidRecord)?;
+                self.recv.rekey(&secret);
+                let record = self.seal_control(CONTROL_REKEY_FINISHED, &[]);
+                self.send.rekey(&secret);
+                self.records_since_rekey = 0;
+                vec![record]
+            }
+            (CONTROL_REKEY_FINISHED, RekeyState::AwaitingFinished(secret)) if body.is_empty() => {
+                self.recv.rekey(&secret);
+                Vec::new()
+            }
+            (_, state) => {
+                self.rekey_state = state;
+                return Err(ChannelError::InvalidRecord);
+            }
+        };
+
+        self.recv_seq = seq;
+        Ok(Incoming::Rekey { reply })
+    }
+
+    pub fn next_recv_seq(&self) -> u64 {
+        self.recv_seq + 1
+    }
+
+    /// Same output as `Session::export_keying_material` for the session this channel was opened over
+    pub fn export_keying_material(
+        &self,
+        label: &[u8],
+        context: &[u8],
+        len: usize,
+    ) -> Result<Zeroizing<Vec<u8>>, ExportError> {
+        let hash = self.cipher_suite.kdf.hash_algorithm();
+        export_keying_material(hash, &self.exporter_master, label, context, len)
+    }
+
+    pub fn channel_binding(&self) -> Vec<u8> {
+        self.channel_binding.clone()
+    }
+
+    fn seal_control(&mut self, control_type: u8, body: &[u8]) -> Record {
+        let mut plaintext = Vec::with_capacity(1 + body.len());
+        plaintext.push(control_type);
+        plaintext.extend_from_slice(body);
+        self.seal(ContentType::Control, &plaintext)
+    }
+
+    fn seal(&mut self, content_type: ContentType, plaintext: &[u8]) -> Record {
         self.send_seq += 1;
+        self.send.records += 1;
+        self.records_since_rekey += 1;
         let seq = self.send_seq;
 
-        let (key, nonce_base) = if self.is_client {
-            (&self.keys.k_client_to_server, &self.keys.nonce_base_c2s)
-        } else {
-            (&self.keys.k_server_to_client, &self.keys.nonce_base_s2c)
+        let key = &self.send.key;
+        let nonce_base = &self.send.nonce_base;
+        let aad = build_aad(seq, content_type, plaintext.len() + TAG_SIZE, &self.transcript);
+        let payload = match self.aead {
+            AeadAlgorithm::ChaCha20Poly1305 => {
+                ChaCha20Poly1305Aead::encrypt(key, nonce_base, seq, &aad, plaintext)
+            }
+            AeadAlgorithm::Aes256Gcm => Aes256GcmAead::encrypt(key, nonce_base, seq, &aad, plaintext),
         };
 
-        let aad = build_aad(seq, &self.transcript);
-        let ciphertext = aead::encrypt(key, nonce_base, seq, &aad, plaintext);
+        Record {
+            content_type,
+            seq: self.config.explicit_sequence_numbers.then_some(seq),
+            payload,
+        }
+    }
+}
+
+/// The server's record layer for the original v1 wire format: `LegacyAppData` frames under
+/// ChaCha20-Poly1305, with `seq ‖ transcript` as additional data. The format has no alerts,
+/// key updates or re-keys, and a stream simply ends.
+pub struct LegacyChannel {
+    keys: SessionKeys,
+    transcript: [u8; 32],
+    send_seq: u64,
+    recv_seq: u64,
+}
+
+impl fmt::Debug for LegacyChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LegacyChannel")
+            .field("send_seq", &self.send_seq)
+            .field("recv_seq", &self.recv_seq)
+            .finish_non_exhaustive()
+    }
+}
+
+fn build_legacy_aad(seq: u64, transcript: &[u8; 32]) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(8 + 32);
+    aad.extend_from_slice(&seq.to_be_bytes());
+    aad.extend_from_slice(transcript);
+    aad
+}
+
+impl LegacyChannel {
+    pub fn new(session: LegacySession) -> Self {
+        Self {
+            keys: session.keys,
+            transcript: session.transcript,
+            send_seq: 0,
+            recv_seq: 0,
+        }
+    }
+
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> LegacyAppData {
+        self.send_seq += 1;
+        let seq = self.send_seq;
+
+        let aad = build_legacy_aad(seq, &self.transcript);
+        let ciphertext = ChaCha20Poly1305Aead::encrypt(
+            &self.keys.k_server_to_client,
+            &self.keys.nonce_base_s2c,
+            seq,
+            &aad,
+            plaintext,
+        );
 
-        AppData { seq, ciphertext }
+        LegacyAppData { seq, ciphertext }
     }
 
-    pub fn decrypt(&mut self, app_data: &AppData) -> Result<Vec<u8>, ChannelError> {
+    /// Decrypt a client frame; sequence numbers must increase, but may skip
+    pub fn decrypt(&mut self, app_data: &LegacyAppData) -> Result<Vec<u8>, ChannelError> {
         if app_data.seq <= self.recv_seq {
             return Err(ChannelError::ReplayDetected);
         }
 
-        let (key, nonce_base) = if self.is_client {
-            (&self.keys.k_server_to_client, &self.keys.nonce_base_s2c)
-        } else {
-            (&self.keys.k_client_to_server, &self.keys.nonce_base_c2s)
-        };
-
-        let aad = build_aad(app_data.seq, &self.transcript);
-        let plaintext = aead::decrypt(key, nonce_base, app_data.seq, &aad, &app_data.ciphertext)
-            .map_err(|_| ChannelError::DecryptionFailed)?;
+        let aad = build_legacy_aad(app_data.seq, &self.transcript);
+        let plaintext = ChaCha20Poly1305Aead::decrypt(
+            &self.keys.k_client_to_server,
+            &self.keys.nonce_base_c2s,
+            app_data.seq,
+            &aad,
+            &app_data.ciphertext,
+        )?;
 
         self.recv_seq = app_data.seq;
         Ok(plaintext)
     }
-
-    pub fn next_recv_seq(&self) -> u64 {
-        self.recv_seq + 1
-    }
 }
@@ -1,10 +1,88 @@
-use sha2::{Digest, Sha256};
+use crypto::hkdf::{HashAlgorithm, RunningHash};
 
-use crate::messages::{ClientHello, ServerHello};
+use crate::messages::{ClientHello, LegacyClientHello, LegacyServerHello, ServerHello};
 
-pub fn compute_transcript(client_hello: &ClientHello, server_hello: &ServerHello) -> [u8; 32] {
-    let mut hasher = Sha256::new();
-    hasher.update(&client_hello.to_bytes());
-    hasher.update(&server_hello.to_bytes());
-    hasher.finalize().into()
+/// Absorbed first, so no transcript hash can collide with a hash computed for another purpose
+const TRANSCRIPT_LABEL: &[u8] = b"hybrid-pq-channel transcript";
+const CHANNEL_BINDING_LABEL: &[u8] = b"hybrid-pq-channel binding";
+
+/// Handshake message types, framed into the transcript ahead of each message (RFC 8446 §4)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum HandshakeType {
+    ClientHello = 1,
+    ServerHello = 2,
+    Finished = 20,
+}
+
+/// Running hash over every handshake message under the selected suite's KDF hash.
+///
+/// Each message is absorbed as `type (1B) ‖ length (4B BE) ‖ message`, after a protocol
+/// label and the length-prefixed application context, so message boundaries are unambiguous
+/// and two different handshakes can never produce the same byte stream. `snapshot` reads the
+/// hash at any stage without ending it: keys and Finished MACs are derived from the
+/// transcript as it stood when they were sent.
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    running: RunningHash,
+}
+
+impl Transcript {
+    /// An empty transcript bound to `context`, which keeps handshakes for different
+    /// applications apart even when every message matches
+    pub fn new(hash: HashAlgorithm, context: &[u8]) -> Self {
+        let len = u32::try_from(context.len()).expect("application context fits a frame");
+        let mut running = hash.running();
+        running.update(&[TRANSCRIPT_LABEL.len() as u8]);
+        running.update(TRANSCRIPT_LABEL);
+        running.update(&len.to_be_bytes());
+        running.update(context);
+        Self { running }
+    }
+
+    /// A transcript through both hellos, the stage the key schedule is derived from. The
+    /// client's offered versions and suites and the server's selection are all covered, so
+    /// a downgrade by a man-in-the-middle changes the derived keys.
+    pub fn from_hellos(
+        context: &[u8],
+        client_hello: &ClientHello,
+        server_hello: &ServerHello,
+    ) -> Self {
+        let mut transcript = Self::new(server_hello.cipher_suite.kdf.hash_algorithm(), context);
+        transcript.absorb(HandshakeType::ClientHello, &client_hello.to_bytes());
+        transcript.absorb(HandshakeType::ServerHello, &server_hello.to_bytes());
+        transcript
+    }
+
+    pub fn absorb(&mut self, message_type: HandshakeType, message: &[u8]) {
+        let len = u32::try_from(message.len()).expect("handshake message fits a frame");
+        self.running.update(&[message_type as u8]);
+        self.running.update(&len.to_be_bytes());
+        self.running.update(message);
+    }
+
+    /// Hash of everything absorbed so far
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.running.snapshot()
+    }
+
+    pub fn hash(&self) -> HashAlgorithm {
+        self.running.algorithm()
+    }
+}
+
+/// Channel binding over the completed handshake: the transcript through both Finished messages
+pub fn compute_channel_binding(hash: HashAlgorithm, transcript: &[u8]) -> Vec<u8> {
+    hash.digest(&[CHANNEL_BINDING_LABEL, transcript].concat())
+}
+
+/// Transcript of the original v1 wire format: SHA-256 over both hellos, with no label,
+/// context or framing, as deployed v1 clients compute it
+pub fn compute_legacy_transcript(
+    client_hello: &LegacyClientHello,
+    server_hello: &LegacyServerHello,
+) -> [u8; 32] {
+    let digest =
+        HashAlgorithm::Sha256.digest(&[client_hello.to_bytes(), server_hello.to_bytes()].concat());
+    digest.try_into().expect("SHA-256 output is 32 bytes")
 }
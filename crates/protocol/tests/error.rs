@@ -0,0 +1,71 @@
+use std::error::Error as _;
+
+use hybrid_kyber_protocol::framing::FrameError;
+use hybrid_kyber_protocol::handshake::{
+    generate_client_hello, handle_client_hello, HandshakeConfig, HandshakeError,
+};
+use hybrid_kyber_protocol::messages::{ClientHello, Extension, MessageError};
+use hybrid_kyber_protocol::session::ChannelError;
+use hybrid_kyber_protocol::{Error, ErrorOrigin};
+
+#[test]
+fn test_decode_error_keeps_postcard_source() {
+    let error = ClientHello::from_bytes(&[0xFF, 0xFF]).unwrap_err();
+    assert!(matches!(error, MessageError::InvalidFormat(_)));
+    assert!(error.source().unwrap().downcast_ref::<postcard::Error>().is_some());
+
+    let error = Error::from(error);
+    assert!(error.is_fatal());
+    assert!(!error.is_retryable());
+    assert_eq!(error.origin(), ErrorOrigin::Peer);
+}
+
+#[test]
+fn test_handshake_error_chains_to_message_error() {
+    let (mut client_hello, _) = generate_client_hello(&HandshakeConfig::default());
+    let ext = Extension {
+        extension_type: 0x7F01,
+        data: vec![],
+    };
+    client_hello.extensions = vec![ext.clone(), ext];
+
+    let error = handle_client_hello(client_hello, &HandshakeConfig::default()).unwrap_err();
+    let source = error.source().unwrap().downcast_ref::<MessageError>().unwrap();
+    assert!(matches!(source, MessageError::DuplicateExtension(0x7F01)));
+    assert_eq!(source.to_string(), "extension 0x7f01 appears more than once");
+
+    let error = Error::from(error);
+    assert_eq!(error.to_string(), "handshake: invalid hello extensions");
+    assert_eq!(error.origin(), ErrorOrigin::Peer);
+}
+
+#[test]
+fn test_error_classification() {
+    let io = Error::from(FrameError::from(std::io::Error::from(std::io::ErrorKind::ConnectionReset)));
+    assert!(io.is_fatal());
+    assert!(io.is_retryable());
+    assert_eq!(io.origin(), ErrorOrigin::Local);
+    assert!(io.source().unwrap().source().is_some());
+
+    let replay = Error::from(ChannelError::ReplayDetected);
+    assert!(!replay.is_fatal());
+    assert_eq!(replay.origin(), ErrorOrigin::Peer);
+
+    let busy = Error::from(ChannelError::RekeyInProgress);
+    assert!(!busy.is_fatal());
+    assert!(busy.is_retryable());
+    assert_eq!(busy.origin(), ErrorOrigin::Local);
+
+    let unsupported = Error::from(ChannelError::RekeyUnsupported);
+    assert!(!unsupported.is_fatal());
+    assert!(!unsupported.is_retryable());
+    assert_eq!(unsupported.origin(), ErrorOrigin::Local);
+
+    let unverified = Error::from(HandshakeError::FinishedNotVerified);
+    assert!(unverified.is_fatal());
+    assert_eq!(unverified.origin(), ErrorOrigin::Local);
+
+    let bad_finished = Error::from(HandshakeError::InvalidFinished);
+    assert!(!bad_finished.is_retryable());
+    assert_eq!(bad_finished.origin(), ErrorOrigin::Peer);
+}
@@ -1,10 +1,67 @@
-use hybrid_kyber_protocol::handshake::{generate_client_hello, handle_client_hello, handle_server_hello};
+use crypto::dhkem::DhKem;
+use crypto::aead::ChaCha20Poly1305Aead;
+use crypto::hkdf::{
+    derive_session_keys, finished_mac, ExportError, HashAlgorithm, KeySchedule, Sha384,
+    MAX_EXPORTER_LABEL_LEN,
+};
+use crypto::hybrid::{ConcatHkdf, HybridKem, HybridPublicKey, HybridSecretKey};
+use crypto::kyber::{Kyber768Kem, MlKem1024Kem, MlKem768Kem};
+use crypto::traits::{Aead, Dh, Kem};
+use crypto::x25519::X25519Dh;
+use crypto::x448::X448Dh;
+use crypto::secret::SecretBytes;
+use hybrid_kyber_protocol::handshake::{
+    generate_client_hello, generate_client_hello_with, generate_client_hello_with_rng,
+    generate_client_hello_with_rng_using, handle_client_hello, handle_client_hello_with,
+    handle_client_hello_with_rng, handle_client_hello_with_rng_using, handle_legacy_client_hello,
+    handle_server_hello,
+    handle_server_hello_with, HandshakeConfig, HandshakeError, NamedGroup, NamedKem,
+    DEFAULT_CIPHER_SUITES, HIGH_SECURITY_CIPHER_SUITES, PROTOCOL_V1, PROTOCOL_V2,
+};
+use hybrid_kyber_protocol::messages::{
+    AeadAlgorithm, Alert, AlertDescription, CipherSuite, ClassicalGroup, Extension, KdfHash,
+    KemAlgorithm, LegacyAppData, LegacyClientHello, LegacyServerHello, MessageError,
+};
+use hybrid_kyber_protocol::session::{ChannelError, Incoming, LegacyChannel, SecureChannel};
+use hybrid_kyber_protocol::transcript::Transcript;
+use rand::rngs::StdRng;
+use rand::{CryptoRng, RngCore, SeedableRng};
 
-#[test]
-fn test_full_handshake() {
-    let (client_hello, client_state) = generate_client_hello();
+fn suite(kem: KemAlgorithm, aead: AeadAlgorithm) -> CipherSuite {
+    CipherSuite {
+        kem,
+        group: ClassicalGroup::X25519,
+        aead,
+        kdf: KdfHash::Sha256,
+    }
+}
+
+fn config_with(cipher_suites: &[CipherSuite]) -> HandshakeConfig {
+    HandshakeConfig {
+        cipher_suites: cipher_suites.to_vec(),
+        ..HandshakeConfig::default()
+    }
+}
+
+fn versions(versions: &[u8]) -> HandshakeConfig {
+    HandshakeConfig {
+        versions: versions.to_vec(),
+        ..HandshakeConfig::default()
+    }
+}
+
+fn run_handshake(cipher_suite: CipherSuite) {
+    let config = config_with(&[cipher_suite]);
+    let (client_hello, client_state) = generate_client_hello(&config);
+    let kem = cipher_suite.kem;
+    assert_eq!(
+        Some(client_hello.kem_share(kem).unwrap().public_key.len()),
+        kem.public_key_size()
+    );
 
-    let (server_hello, server_session) = handle_client_hello(client_hello).unwrap();
+    let (server_hello, server_session) = handle_client_hello(client_hello, &config).unwrap();
+    assert_eq!(server_hello.cipher_suite, cipher_suite);
+    assert_eq!(Some(server_hello.kyber_ct.len()), kem.ciphertext_size());
 
     let client_session = handle_server_hello(server_hello, client_state).unwrap();
 
@@ -17,4 +74,961 @@ fn test_full_handshake() {
         server_session.keys.k_server_to_client
     );
     assert_eq!(client_session.transcript, server_session.transcript);
+    assert_eq!(client_session.cipher_suite, server_session.cipher_suite);
+}
+
+#[test]
+fn test_full_handshake() {
+    run_handshake(CipherSuite::KYBER768_X25519_CHACHA20POLY1305_SHA256);
+}
+
+#[test]
+fn test_full_handshake_mlkem768() {
+    run_handshake(CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256);
+}
+
+#[test]
+fn test_full_handshake_all_parameter_sets() {
+    for kem in [
+        KemAlgorithm::KYBER512,
+        KemAlgorithm::KYBER1024,
+        KemAlgorithm::MLKEM512,
+        KemAlgorithm::MLKEM1024,
+    ] {
+        run_handshake(suite(kem, AeadAlgorithm::Aes256Gcm));
+    }
+}
+
+#[test]
+fn test_full_handshake_p256_and_x448() {
+    run_handshake(CipherSuite::MLKEM768_P256_AES256GCM_SHA256);
+    run_handshake(CipherSuite::MLKEM768_X448_CHACHA20POLY1305_SHA256);
+}
+
+#[test]
+fn test_one_dh_share_per_group() {
+    let client_config = config_with(&[
+        CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256,
+        CipherSuite::MLKEM768_X448_CHACHA20POLY1305_SHA256,
+        CipherSuite::MLKEM768_P256_AES256GCM_SHA256,
+        CipherSuite::MLKEM768_X25519_AES256GCM_SHA256,
+    ]);
+    let (client_hello, client_state) = generate_client_hello(&client_config);
+    assert_eq!(client_hello.kem_shares.len(), 1);
+    let groups: Vec<_> = client_hello.dh_shares.iter().map(|share| share.group).collect();
+    assert_eq!(
+        groups,
+        vec![ClassicalGroup::X25519, ClassicalGroup::X448, ClassicalGroup::P256]
+    );
+    for share in &client_hello.dh_shares {
+        assert_eq!(Some(share.public_key.len()), share.group.public_key_size());
+    }
+
+    // A server bound to NIST curves picks P-256 even though the client prefers X25519
+    let server_config = config_with(&[CipherSuite::MLKEM768_P256_AES256GCM_SHA256]);
+    let (server_hello, server_session) = handle_client_hello(client_hello, &server_config).unwrap();
+    assert_eq!(server_hello.dh_public_key.len(), 65);
+    let client_session = handle_server_hello(server_hello, client_state).unwrap();
+    assert_eq!(client_session.cipher_suite.group, ClassicalGroup::P256);
+    assert_eq!(client_session.transcript, server_session.transcript);
+}
+
+#[test]
+fn test_dh_share_size_validated() {
+    let config = config_with(&[CipherSuite::MLKEM768_X448_CHACHA20POLY1305_SHA256]);
+    let (mut client_hello, _) = generate_client_hello(&config);
+    client_hello.dh_shares[0].public_key.truncate(32);
+    assert!(matches!(
+        handle_client_hello(client_hello, &config),
+        Err(HandshakeError::InvalidKeySize)
+    ));
+
+    let (client_hello, client_state) = generate_client_hello(&config);
+    let (mut server_hello, _) = handle_client_hello(client_hello, &config).unwrap();
+    server_hello.dh_public_key.push(0);
+    assert!(matches!(
+        handle_server_hello(server_hello, client_state),
+        Err(HandshakeError::InvalidKeySize)
+    ));
+}
+
+/// An X25519 point of order 8, which forces the shared secret to zero
+const LOW_ORDER_X25519: [u8; 32] = [
+    0xe0, 0xeb, 0x7a, 0x7c, 0x3b, 0x41, 0xb8, 0xae, 0x16, 0x56, 0xe3, 0xfa, 0xf1, 0x9f, 0xc4, 0x6a,
+    0xda, 0x09, 0x8d, 0xeb, 0x9c, 0x32, 0xb1, 0xfd, 0x86, 0x62, 0x05, 0x16, 0x5f, 0x49, 0xb8, 0x00,
+];
+
+#[test]
+fn test_low_order_x25519_shares_rejected() {
+    let config = config_with(&[CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256]);
+    let (mut client_hello, _) = generate_client_hello(&config);
+    client_hello.dh_shares[0].public_key = LOW_ORDER_X25519.to_vec();
+    assert!(matches!(
+        handle_client_hello(client_hello, &config),
+        Err(HandshakeError::LowOrderPoint)
+    ));
+
+    let (client_hello, client_state) = generate_client_hello(&config);
+    let (mut server_hello, _) = handle_client_hello(client_hello, &config).unwrap();
+    server_hello.dh_public_key = vec![0; 32];
+    assert!(matches!(
+        handle_server_hello(server_hello, client_state),
+        Err(HandshakeError::LowOrderPoint)
+    ));
+}
+
+#[test]
+fn test_unreduced_kem_share_rejected() {
+    let config = config_with(&[CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256]);
+    let (mut client_hello, _) = generate_client_hello(&config);
+    // Raise the first 12-bit coefficient to 0xfff, above q
+    client_hello.kem_shares[0].public_key[0] = 0xff;
+    client_hello.kem_shares[0].public_key[1] |= 0x0f;
+    assert!(matches!(
+        handle_client_hello(client_hello, &config),
+        Err(HandshakeError::KemModulusCheckFailed)
+    ));
+}
+
+#[test]
+fn test_key_size_follows_parameter_set() {
+    let (mut client_hello, _) =
+        generate_client_hello(&config_with(&[CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256]));
+    let mlkem1024 = suite(KemAlgorithm::MLKEM1024, AeadAlgorithm::ChaCha20Poly1305);
+    client_hello.cipher_suites = vec![mlkem1024];
+    client_hello.kem_shares[0].kem = KemAlgorithm::MLKEM1024;
+
+    let result = handle_client_hello(client_hello, &config_with(&[mlkem1024]));
+    assert!(matches!(result, Err(HandshakeError::InvalidKeySize)));
+}
+
+#[test]
+fn test_server_preference_wins() {
+    let client_config = config_with(&[
+        CipherSuite::KYBER768_X25519_CHACHA20POLY1305_SHA256,
+        CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256,
+        CipherSuite::MLKEM768_X25519_AES256GCM_SHA256,
+    ]);
+    let server_config = config_with(&[
+        CipherSuite::MLKEM768_X25519_AES256GCM_SHA256,
+        CipherSuite::KYBER768_X25519_CHACHA20POLY1305_SHA256,
+    ]);
+
+    let (client_hello, client_state) = generate_client_hello(&client_config);
+    assert_eq!(client_hello.kem_shares.len(), 2);
+
+    let (server_hello, _) = handle_client_hello(client_hello, &server_config).unwrap();
+    assert_eq!(
+        server_hello.cipher_suite,
+        CipherSuite::MLKEM768_X25519_AES256GCM_SHA256
+    );
+
+    let session = handle_server_hello(server_hello, client_state).unwrap();
+    assert_eq!(session.cipher_suite.aead, AeadAlgorithm::Aes256Gcm);
+}
+
+#[test]
+fn test_no_common_suite_rejected() {
+    let (client_hello, _) =
+        generate_client_hello(&config_with(&[CipherSuite::KYBER768_X25519_CHACHA20POLY1305_SHA256]));
+
+    let result = handle_client_hello(
+        client_hello,
+        &config_with(&[CipherSuite::MLKEM768_X25519_AES256GCM_SHA256]),
+    );
+    assert!(matches!(result, Err(HandshakeError::NoCommonSuite)));
+}
+
+#[test]
+fn test_unoffered_suite_rejected() {
+    let config = HandshakeConfig::default();
+    let (client_hello, client_state) = generate_client_hello(&config);
+    let (mut server_hello, _) = handle_client_hello(client_hello, &config).unwrap();
+    server_hello.cipher_suite = suite(KemAlgorithm::KYBER512, AeadAlgorithm::ChaCha20Poly1305);
+
+    let result = handle_server_hello(server_hello, client_state);
+    assert!(matches!(result, Err(HandshakeError::UnofferedSuite)));
+}
+
+#[test]
+fn test_server_speaks_v1_and_v2() {
+    let server_config = HandshakeConfig::default();
+
+    for (client_versions, expected) in [
+        (vec![PROTOCOL_V2, PROTOCOL_V1], PROTOCOL_V2),
+        (vec![PROTOCOL_V1], PROTOCOL_V1),
+        (vec![PROTOCOL_V2], PROTOCOL_V2),
+    ] {
+        let (client_hello, client_state) = generate_client_hello(&versions(&client_versions));
+        let (server_hello, server_session) =
+            handle_client_hello(client_hello, &server_config).unwrap();
+        assert_eq!(server_hello.version, expected);
+
+        let client_session = handle_server_hello(server_hello, client_state).unwrap();
+        assert_eq!(client_session.version, expected);
+        assert_eq!(
+            client_session.keys.k_client_to_server,
+            server_session.keys.k_client_to_server
+        );
+    }
+}
+
+#[test]
+fn test_no_common_version_rejected() {
+    let (client_hello, _) = generate_client_hello(&versions(&[PROTOCOL_V1]));
+
+    let result = handle_client_hello(client_hello, &versions(&[PROTOCOL_V2]));
+    assert!(matches!(result, Err(HandshakeError::NoCommonVersion)));
+}
+
+#[test]
+fn test_unoffered_version_rejected() {
+    let (client_hello, client_state) = generate_client_hello(&versions(&[PROTOCOL_V2]));
+    let (mut server_hello, _) =
+        handle_client_hello(client_hello, &HandshakeConfig::default()).unwrap();
+    server_hello.version = PROTOCOL_V1;
+
+    let result = handle_server_hello(server_hello, client_state);
+    assert!(matches!(result, Err(HandshakeError::UnofferedVersion)));
+}
+
+#[test]
+fn test_stripped_version_yields_mismatched_keys() {
+    let config = HandshakeConfig::default();
+    let (mut client_hello, client_state) = generate_client_hello(&config);
+    client_hello.versions = vec![PROTOCOL_V1];
+
+    let (server_hello, server_session) = handle_client_hello(client_hello, &config).unwrap();
+    assert_eq!(server_hello.version, PROTOCOL_V1);
+
+    let mut client_session = handle_server_hello(server_hello, client_state).unwrap();
+    assert_ne!(client_session.transcript, server_session.transcript);
+    assert_ne!(
+        client_session.keys.k_client_to_server,
+        server_session.keys.k_client_to_server
+    );
+    assert!(matches!(
+        client_session.verify_finished(&server_session.finished()),
+        Err(HandshakeError::InvalidFinished)
+    ));
+}
+
+#[test]
+fn test_finished_confirms_both_sides() {
+    let config = HandshakeConfig::default();
+    let (client_hello, client_state) = generate_client_hello(&config);
+    let (server_hello, mut server_session) = handle_client_hello(client_hello, &config).unwrap();
+    let mut client_session = handle_server_hello(server_hello, client_state).unwrap();
+
+    let server_finished = server_session.finished();
+    let client_finished = client_session.finished();
+    assert_ne!(server_finished.verify_data, client_finished.verify_data);
+
+    client_session.verify_finished(&server_finished).unwrap();
+    server_session.verify_finished(&client_finished).unwrap();
+    assert!(client_session.is_confirmed());
+    assert!(server_session.is_confirmed());
+}
+
+#[test]
+fn test_tampered_finished_rejected() {
+    let config = HandshakeConfig::default();
+    let (client_hello, client_state) = generate_client_hello(&config);
+    let (server_hello, server_session) = handle_client_hello(client_hello, &config).unwrap();
+    let mut client_session = handle_server_hello(server_hello, client_state).unwrap();
+
+    let mut finished = server_session.finished();
+    finished.verify_data[0] ^= 0x01;
+    assert!(matches!(
+        client_session.verify_finished(&finished),
+        Err(HandshakeError::InvalidFinished)
+    ));
+    assert!(!client_session.is_confirmed());
+}
+
+#[test]
+fn test_own_finished_not_accepted_as_peer() {
+    let config = HandshakeConfig::default();
+    let (client_hello, client_state) = generate_client_hello(&config);
+    let (server_hello, _) = handle_client_hello(client_hello, &config).unwrap();
+    let mut client_session = handle_server_hello(server_hello, client_state).unwrap();
+
+    let reflected = client_session.finished();
+    assert!(matches!(
+        client_session.verify_finished(&reflected),
+        Err(HandshakeError::InvalidFinished)
+    ));
+}
+
+#[test]
+fn test_extensions_reach_peer() {
+    let client_ext = Extension {
+        extension_type: 0x7F01,
+        data: b"client".to_vec(),
+    };
+    let server_ext = Extension {
+        extension_type: 0x7F02,
+        data: b"server".to_vec(),
+    };
+    let client_config = HandshakeConfig {
+        extensions: vec![client_ext.clone()],
+        ..HandshakeConfig::default()
+    };
+    let server_config = HandshakeConfig {
+        extensions: vec![server_ext.clone()],
+        ..HandshakeConfig::default()
+    };
+
+    let (client_hello, client_state) = generate_client_hello(&client_config);
+    let (server_hello, server_session) = handle_client_hello(client_hello, &server_config).unwrap();
+    let client_session = handle_server_hello(server_hello, client_state).unwrap();
+
+    assert_eq!(server_session.peer_extensions, vec![client_ext]);
+    assert_eq!(client_session.peer_extensions, vec![server_ext]);
+    assert_eq!(client_session.transcript, server_session.transcript);
+}
+
+#[test]
+fn test_duplicate_client_extensions_rejected() {
+    let (mut client_hello, _) = generate_client_hello(&HandshakeConfig::default());
+    let ext = Extension {
+        extension_type: 0x7F01,
+        data: vec![],
+    };
+    client_hello.extensions = vec![ext.clone(), ext];
+
+    let result = handle_client_hello(client_hello, &HandshakeConfig::default());
+    assert!(matches!(
+        result,
+        Err(HandshakeError::InvalidExtensions(MessageError::DuplicateExtension(0x7F01)))
+    ));
+}
+
+#[test]
+fn test_exporter_independent_of_traffic_keys() {
+    let config = HandshakeConfig::default();
+    let (client_hello, client_state) = generate_client_hello(&config);
+    let (server_hello, server_session) = handle_client_hello(client_hello, &config).unwrap();
+    let client_session = handle_server_hello(server_hello, client_state).unwrap();
+
+    let ekm = client_session.export_keying_material(b"app auth", b"", 32).unwrap();
+    assert_eq!(ekm, server_session.export_keying_material(b"app auth", b"", 32).unwrap());
+    assert_ne!(ekm[..], client_session.keys.k_client_to_server[..]);
+    assert_ne!(ekm[..], client_session.keys.k_server_to_client[..]);
+    assert_eq!(client_session.channel_binding(), server_session.channel_binding());
+}
+
+#[test]
+fn test_exporter_rejects_oversized_requests() {
+    let config = HandshakeConfig::default();
+    let (client_hello, client_state) = generate_client_hello(&config);
+    let (server_hello, _) = handle_client_hello(client_hello, &config).unwrap();
+    let session = handle_server_hello(server_hello, client_state).unwrap();
+
+    let label = [b'l'; MAX_EXPORTER_LABEL_LEN + 1];
+    assert!(session.export_keying_material(&label[1..], b"", 32).is_ok());
+    assert_eq!(
+        session.export_keying_material(&label, b"", 32).unwrap_err(),
+        ExportError::LabelTooLong
+    );
+
+    assert_eq!(session.export_keying_material(b"app auth", b"", 255 * 32).unwrap().len(), 8160);
+    for len in [255 * 32 + 1, usize::MAX] {
+        assert_eq!(
+            session.export_keying_material(b"app auth", b"", len).unwrap_err(),
+            ExportError::LengthTooLong
+        );
+    }
+
+    // The context is hashed before it reaches HKDF-Expand-Label, so its length is unbounded
+    assert!(session.export_keying_material(b"app auth", &[0u8; 1024], 32).is_ok());
+}
+
+#[test]
+fn test_generic_handshake_offers_only_its_primitives() {
+    let config = HandshakeConfig::default();
+    let (client_hello, client_state) = generate_client_hello_with::<MlKem768Kem, X25519Dh>(&config);
+    assert_eq!(client_hello.kem_shares.len(), 1);
+    assert!(client_hello
+        .cipher_suites
+        .iter()
+        .all(|suite| suite.kem == KemAlgorithm::MLKEM768));
+
+    // A negotiating server and a generic client interoperate
+    let (server_hello, server_session) = handle_client_hello(client_hello, &config).unwrap();
+    let client_session =
+        handle_server_hello_with::<MlKem768Kem, X25519Dh>(server_hello, client_state).unwrap();
+    assert_eq!(client_session.transcript, server_session.transcript);
+    assert_eq!(
+        client_session.keys.k_client_to_server,
+        server_session.keys.k_client_to_server
+    );
+}
+
+#[test]
+fn test_generic_server_rejects_other_kems() {
+    let config = HandshakeConfig::default();
+    let (client_hello, client_state) = generate_client_hello(&config);
+    let (server_hello, server_session) =
+        handle_client_hello_with::<Kyber768Kem, X25519Dh>(client_hello.clone(), &config).unwrap();
+    assert_eq!(
+        server_hello.cipher_suite,
+        CipherSuite::KYBER768_X25519_CHACHA20POLY1305_SHA256
+    );
+    let client_session = handle_server_hello(server_hello, client_state).unwrap();
+    assert_eq!(client_session.transcript, server_session.transcript);
+
+    let mlkem_only = config_with(&[CipherSuite::MLKEM768_X25519_AES256GCM_SHA256]);
+    let result = handle_client_hello_with::<Kyber768Kem, X25519Dh>(client_hello, &mlkem_only);
+    assert!(matches!(result, Err(HandshakeError::NoCommonSuite)));
+}
+
+#[test]
+fn test_high_security_profile_handshake() {
+    let config = HandshakeConfig::high_security();
+    let (client_hello, client_state) = generate_client_hello(&config);
+    assert_eq!(client_hello.versions, vec![PROTOCOL_V2]);
+    assert_eq!(client_hello.kem_shares.len(), 1);
+    assert_eq!(client_hello.dh_shares.len(), 1);
+    assert_eq!(
+        client_hello.dh_share(ClassicalGroup::X448).unwrap().public_key.len(),
+        56
+    );
+
+    let (server_hello, mut server_session) = handle_client_hello(client_hello, &config).unwrap();
+    assert_eq!(
+        server_hello.cipher_suite,
+        CipherSuite::MLKEM1024_X448_AES256GCM_SHA384
+    );
+    let mut client_session = handle_server_hello(server_hello, client_state).unwrap();
+
+    // Transcript and Finished MACs are SHA-384 sized
+    assert_eq!(client_session.transcript.len(), 48);
+    assert_eq!(client_session.transcript, server_session.transcript);
+    assert_eq!(client_session.finished().verify_data.len(), 48);
+    assert_eq!(
+        client_session.keys.k_client_to_server,
+        server_session.keys.k_client_to_server
+    );
+
+    client_session
+        .verify_finished(&server_session.finished())
+        .unwrap();
+    server_session
+        .verify_finished(&client_session.finished())
+        .unwrap();
+    assert_eq!(client_session.channel_binding().len(), 48);
+    assert_eq!(
+        client_session.export_keying_material(b"app auth", b"", 64).unwrap(),
+        server_session.export_keying_material(b"app auth", b"", 64).unwrap()
+    );
+}
+
+#[test]
+fn test_high_security_suite_uses_sha384_at_every_layer() {
+    type Sha384Hybrid = HybridKem<MlKem1024Kem, DhKem<X448Dh>, ConcatHkdf<Sha384>>;
+    let sha384 = HashAlgorithm::Sha384;
+    let config = HandshakeConfig::high_security();
+    let (client_hello, client_state) =
+        generate_client_hello_with_rng(&config, &mut StdRng::seed_from_u64(7));
+
+    // Replay the client's draws to recover the secret halves of its shares
+    let mut rng = StdRng::seed_from_u64(7);
+    let (kem_pk, kem_sk) = MlKem1024Kem::generate_keypair_with_rng(&mut rng);
+    let (dh_pk, dh_sk) = X448Dh::generate_keypair_with_rng(&mut rng);
+    assert_eq!(
+        client_hello.kem_share(KemAlgorithm::MLKEM1024).unwrap().public_key,
+        MlKem1024Kem::public_key_to_bytes(&kem_pk)
+    );
+    assert_eq!(
+        client_hello.dh_share(ClassicalGroup::X448).unwrap().public_key,
+        X448Dh::public_key_to_bytes(&dh_pk)
+    );
+
+    let (server_hello, mut server_session) =
+        handle_client_hello(client_hello.clone(), &config).unwrap();
+    assert_eq!(server_hello.cipher_suite.kdf, KdfHash::Sha384);
+
+    // Combiner: HKDF-SHA384 over both component secrets, one SHA-384 output long
+    let sk = HybridSecretKey::<MlKem1024Kem, DhKem<X448Dh>> {
+        a: kem_sk,
+        b: dh_sk,
+        public: HybridPublicKey { a: kem_pk, b: dh_pk },
+    };
+    let ct = [&server_hello.kyber_ct[..], &server_hello.dh_public_key[..]].concat();
+    let ct = Sha384Hybrid::ciphertext_from_bytes(&ct).unwrap();
+    let shared_secret = Sha384Hybrid::decapsulate(&sk, &ct).unwrap();
+    assert_eq!(shared_secret.len(), 48);
+
+    // Transcript
+    let transcript = Transcript::from_hellos(&config.context, &client_hello, &server_hello);
+    assert_eq!(transcript.hash(), sha384);
+    let mut client_session = handle_server_hello(server_hello, client_state).unwrap();
+    assert_eq!(client_session.transcript, transcript.snapshot());
+
+    // Key schedule, keyed by the SHA-384 combiner output
+    let schedule = KeySchedule::new(sha384, &shared_secret, &client_session.transcript);
+    let keys = schedule.session_keys();
+    for session in [&client_session, &server_session] {
+        assert_eq!(session.keys.k_client_to_server, keys.k_client_to_server);
+        assert_eq!(session.keys.k_server_to_client, keys.k_server_to_client);
+        assert_eq!(session.keys.nonce_base_c2s, keys.nonce_base_c2s);
+        assert_eq!(session.keys.nonce_base_s2c, keys.nonce_base_s2c);
+    }
+
+    // Finished MACs, exporter and channel binding
+    let server_finished = server_session.finished();
+    assert_eq!(
+        server_finished.verify_data,
+        finished_mac(sha384, &schedule.finished_keys().server, &client_session.transcript)
+    );
+    client_session.verify_finished(&server_finished).unwrap();
+    server_session.verify_finished(&client_session.finished()).unwrap();
+    assert_eq!(
+        client_session.export_keying_material(b"app auth", b"", 64).unwrap(),
+        schedule.export_keying_material(b"app auth", b"", 64).unwrap()
+    );
+    assert_eq!(client_session.channel_binding().len(), 48);
+}
+
+#[test]
+fn test_high_security_client_gets_profile_from_permissive_server() {
+    let server_config = config_with(&[DEFAULT_CIPHER_SUITES, HIGH_SECURITY_CIPHER_SUITES].concat());
+    let (client_hello, client_state) = generate_client_hello(&HandshakeConfig::high_security());
+    let (server_hello, server_session) = handle_client_hello(client_hello, &server_config).unwrap();
+    assert_eq!(server_hello.version, PROTOCOL_V2);
+    assert_eq!(server_hello.cipher_suite.kem, KemAlgorithm::MLKEM1024);
+    assert_eq!(server_hello.cipher_suite.kdf, KdfHash::Sha384);
+
+    let client_session = handle_server_hello(server_hello, client_state).unwrap();
+    assert_eq!(client_session.transcript, server_session.transcript);
+}
+
+#[test]
+fn test_high_security_client_refuses_weaker_suites() {
+    // A client that demands the profile never offers suites outside it, even if configured
+    let config = HandshakeConfig {
+        versions: vec![PROTOCOL_V2, PROTOCOL_V1],
+        cipher_suites: [DEFAULT_CIPHER_SUITES, HIGH_SECURITY_CIPHER_SUITES].concat(),
+        ..HandshakeConfig::high_security()
+    };
+    let (client_hello, client_state) = generate_client_hello(&config);
+    assert_eq!(client_hello.versions, vec![PROTOCOL_V2]);
+    assert_eq!(client_hello.cipher_suites, HIGH_SECURITY_CIPHER_SUITES);
+
+    let result = handle_client_hello(client_hello, &HandshakeConfig::default());
+    assert!(matches!(result, Err(HandshakeError::NoCommonSuite)));
+
+    // A server answering with a weaker suite anyway is rejected
+    let (weak_hello, _) = generate_client_hello(&HandshakeConfig::default());
+    let (server_hello, _) = handle_client_hello(weak_hello, &HandshakeConfig::default()).unwrap();
+    let result = handle_server_hello(server_hello, client_state);
+    assert!(matches!(result, Err(HandshakeError::UnofferedSuite)));
+}
+
+#[test]
+fn test_high_security_server_enforces_profile() {
+    let server_config = HandshakeConfig {
+        cipher_suites: [DEFAULT_CIPHER_SUITES, HIGH_SECURITY_CIPHER_SUITES].concat(),
+        versions: vec![PROTOCOL_V2, PROTOCOL_V1],
+        ..HandshakeConfig::high_security()
+    };
+
+    let (client_hello, _) = generate_client_hello(&HandshakeConfig::default());
+    let result = handle_client_hello(client_hello, &server_config);
+    assert!(matches!(result, Err(HandshakeError::NoCommonSuite)));
+
+    let v1_client = HandshakeConfig {
+        versions: vec![PROTOCOL_V1],
+        cipher_suites: HIGH_SECURITY_CIPHER_SUITES.to_vec(),
+        ..HandshakeConfig::default()
+    };
+    let (client_hello, _) = generate_client_hello(&v1_client);
+    let result = handle_client_hello(client_hello, &server_config);
+    assert!(matches!(result, Err(HandshakeError::NoCommonVersion)));
+}
+
+/// Hellos and client-to-server keys from a handshake seeded with `seed`
+fn seeded_handshake(config: &HandshakeConfig, seed: u64) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+    let mut client_rng = StdRng::seed_from_u64(seed);
+    let mut server_rng = StdRng::seed_from_u64(seed + 1);
+    let (client_hello, client_state) = generate_client_hello_with_rng(config, &mut client_rng);
+    let client_hello_bytes = client_hello.to_bytes();
+    let (server_hello, server_session) =
+        handle_client_hello_with_rng(client_hello, config, &mut server_rng).unwrap();
+    let server_hello_bytes = server_hello.to_bytes();
+    let client_session = handle_server_hello(server_hello, client_state).unwrap();
+    assert_eq!(
+        client_session.keys.k_client_to_server,
+        server_session.keys.k_client_to_server
+    );
+
+    let keys = &client_session.keys;
+    let key_bytes = [
+        &keys.k_client_to_server[..],
+        &keys.k_server_to_client[..],
+        &keys.nonce_base_c2s[..],
+        &keys.nonce_base_s2c[..],
+    ]
+    .concat();
+    (client_hello_bytes, server_hello_bytes, key_bytes)
+}
+
+#[test]
+fn test_seeded_handshake_is_reproducible() {
+    let config = config_with(&[
+        CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256,
+        CipherSuite::MLKEM768_P256_AES256GCM_SHA256,
+        CipherSuite::MLKEM768_X448_CHACHA20POLY1305_SHA256,
+    ]);
+    let first = seeded_handshake(&config, 42);
+    assert_eq!(first, seeded_handshake(&config, 42));
+    assert_ne!(first, seeded_handshake(&config, 43));
+
+    // The default suites, Kyber among them, and a Kyber-only handshake
+    let default = HandshakeConfig::default();
+    assert_eq!(seeded_handshake(&default, 42), seeded_handshake(&default, 42));
+    let kyber = config_with(&[CipherSuite::KYBER768_X25519_CHACHA20POLY1305_SHA256]);
+    assert_eq!(seeded_handshake(&kyber, 42), seeded_handshake(&kyber, 42));
+    assert_ne!(seeded_handshake(&kyber, 42), seeded_handshake(&kyber, 43));
+
+    let high_security = HandshakeConfig::high_security();
+    assert_eq!(
+        seeded_handshake(&high_security, 42),
+        seeded_handshake(&high_security, 42)
+    );
+}
+
+#[test]
+fn test_handshake_errors_map_to_alerts() {
+    let config = config_with(&[CipherSuite::MLKEM768_X448_CHACHA20POLY1305_SHA256]);
+    let (mut client_hello, _) = generate_client_hello(&config);
+    client_hello.dh_shares[0].public_key.truncate(32);
+    let error = handle_client_hello(client_hello, &config).unwrap_err();
+    assert_eq!(error.alert(), AlertDescription::IllegalParameter);
+
+    assert_eq!(HandshakeError::NoCommonSuite.alert(), AlertDescription::HandshakeFailure);
+    assert_eq!(HandshakeError::NoCommonVersion.alert(), AlertDescription::ProtocolVersion);
+    assert_eq!(HandshakeError::InvalidFinished.alert(), AlertDescription::DecryptError);
+
+    // Sent in the clear, an alert round-trips as its registry code
+    let alert = Alert::new(AlertDescription::HandshakeFailure);
+    assert_eq!(alert.to_bytes(), vec![40]);
+    assert_eq!(Alert::from_bytes(&alert.to_bytes()).unwrap(), alert);
+    assert!(Alert::from_bytes(&[41]).is_err());
+}
+
+fn with_context(context: &[u8]) -> HandshakeConfig {
+    HandshakeConfig {
+        context: context.to_vec(),
+        ..HandshakeConfig::default()
+    }
+}
+
+#[test]
+fn test_matching_context_completes_handshake() {
+    let config = with_context(b"service-a");
+    let (client_hello, client_state) = generate_client_hello(&config);
+    let (server_hello, mut server) = handle_client_hello(client_hello, &config).unwrap();
+    let mut client = handle_server_hello(server_hello, client_state).unwrap();
+
+    client.verify_finished(&server.finished()).unwrap();
+    server.verify_finished(&client.finished()).unwrap();
+    assert_eq!(client.transcript, server.transcript);
+    assert_eq!(client.keys.k_client_to_server, server.keys.k_client_to_server);
+}
+
+#[test]
+fn test_mismatched_context_fails_finished() {
+    let (client_hello, client_state) = generate_client_hello(&with_context(b"service-a"));
+    let (server_hello, mut server) =
+        handle_client_hello(client_hello, &with_context(b"service-b")).unwrap();
+    let mut client = handle_server_hello(server_hello, client_state).unwrap();
+
+    assert_ne!(client.transcript, server.transcript);
+    assert_ne!(client.keys.k_client_to_server, server.keys.k_client_to_server);
+
+    let error = client.verify_finished(&server.finished()).unwrap_err();
+    assert!(matches!(error, HandshakeError::InvalidFinished));
+    assert_eq!(error.alert(), AlertDescription::DecryptError);
+    assert!(matches!(
+        server.verify_finished(&client.finished()),
+        Err(HandshakeError::InvalidFinished)
+    ));
+    assert!(!client.is_confirmed() && !server.is_confirmed());
+}
+
+/// A KEM from outside this workspace, standing in behind a private-use code point
+struct ThirdPartyKem;
+
+impl Kem for ThirdPartyKem {
+    type PublicKey = <MlKem768Kem as Kem>::PublicKey;
+    type SecretKey = <MlKem768Kem as Kem>::SecretKey;
+    type Ciphertext = <MlKem768Kem as Kem>::Ciphertext;
+    type SharedSecret = SecretBytes;
+
+    const PUBLIC_KEY_SIZE: usize = MlKem768Kem::PUBLIC_KEY_SIZE;
+    const SECRET_KEY_SIZE: usize = MlKem768Kem::SECRET_KEY_SIZE;
+    const CIPHERTEXT_SIZE: usize = MlKem768Kem::CIPHERTEXT_SIZE;
+    const SHARED_SECRET_SIZE: usize = MlKem768Kem::SHARED_SECRET_SIZE;
+
+    fn generate_keypair_with_rng<R: CryptoRng + RngCore>(
+        rng: &mut R,
+    ) -> (Self::PublicKey, Self::SecretKey) {
+        MlKem768Kem::generate_keypair_with_rng(rng)
+    }
+
+    fn encapsulate_with_rng<R: CryptoRng + RngCore>(
+        pk: &Self::PublicKey,
+        rng: &mut R,
+    ) -> Result<(Self::Ciphertext, Self::SharedSecret), crypto::traits::KemError> {
+        MlKem768Kem::encapsulate_with_rng(pk, rng)
+    }
+
+    fn decapsulate(
+        sk: &Self::SecretKey,
+        ct: &Self::Ciphertext,
+    ) -> Result<Self::SharedSecret, crypto::traits::KemError> {
+        MlKem768Kem::decapsulate(sk, ct)
+    }
+
+    fn public_key_to_bytes(pk: &Self::PublicKey) -> Vec<u8> {
+        MlKem768Kem::public_key_to_bytes(pk)
+    }
+
+    fn public_key_from_bytes(bytes: &[u8]) -> Result<Self::PublicKey, crypto::traits::KemError> {
+        MlKem768Kem::public_key_from_bytes(bytes)
+    }
+
+    fn secret_key_to_bytes(sk: &Self::SecretKey) -> SecretBytes {
+        MlKem768Kem::secret_key_to_bytes(sk)
+    }
+
+    fn secret_key_from_bytes(bytes: &[u8]) -> Result<Self::SecretKey, crypto::traits::KemError> {
+        MlKem768Kem::secret_key_from_bytes(bytes)
+    }
+
+    fn ciphertext_to_bytes(ct: &Self::Ciphertext) -> Vec<u8> {
+        MlKem768Kem::ciphertext_to_bytes(ct)
+    }
+
+    fn ciphertext_from_bytes(bytes: &[u8]) -> Result<Self::Ciphertext, crypto::traits::KemError> {
+        MlKem768Kem::ciphertext_from_bytes(bytes)
+    }
+}
+
+impl NamedKem for ThirdPartyKem {
+    const ALGORITHM: KemAlgorithm = KemAlgorithm(0xfe00);
+}
+
+/// A group from outside this workspace, likewise under a private-use code point
+struct ThirdPartyDh;
+
+impl Dh for ThirdPartyDh {
+    type SecretKey = <X25519Dh as Dh>::SecretKey;
+    type PublicKey = <X25519Dh as Dh>::PublicKey;
+    type SharedSecret = <X25519Dh as Dh>::SharedSecret;
+
+    const PUBLIC_KEY_SIZE: usize = X25519Dh::PUBLIC_KEY_SIZE;
+    const SECRET_KEY_SIZE: usize = X25519Dh::SECRET_KEY_SIZE;
+    const SHARED_SECRET_SIZE: usize = X25519Dh::SHARED_SECRET_SIZE;
+
+    fn generate_keypair_with_rng<R: CryptoRng + RngCore>(
+        rng: &mut R,
+    ) -> (Self::PublicKey, Self::SecretKey) {
+        X25519Dh::generate_keypair_with_rng(rng)
+    }
+
+    fn public_key(sk: &Self::SecretKey) -> Self::PublicKey {
+        X25519Dh::public_key(sk)
+    }
+
+    fn diffie_hellman(sk: &Self::SecretKey, pk: &Self::PublicKey) -> Self::SharedSecret {
+        X25519Dh::diffie_hellman(sk, pk)
+    }
+
+    fn public_key_to_bytes(pk: &Self::PublicKey) -> Vec<u8> {
+        X25519Dh::public_key_to_bytes(pk)
+    }
+
+    fn public_key_from_bytes(bytes: &[u8]) -> Option<Self::PublicKey> {
+        X25519Dh::public_key_from_bytes(bytes)
+    }
+
+    fn secret_key_to_bytes(sk: &Self::SecretKey) -> SecretBytes {
+        X25519Dh::secret_key_to_bytes(sk)
+    }
+
+    fn secret_key_from_bytes(bytes: &[u8]) -> Option<Self::SecretKey> {
+        X25519Dh::secret_key_from_bytes(bytes)
+    }
+}
+
+impl NamedGroup for ThirdPartyDh {
+    const GROUP: ClassicalGroup = ClassicalGroup(0xfe00);
+}
+
+fn third_party_suite() -> CipherSuite {
+    CipherSuite {
+        kem: ThirdPartyKem::ALGORITHM,
+        group: ThirdPartyDh::GROUP,
+        aead: AeadAlgorithm::ChaCha20Poly1305,
+        kdf: KdfHash::Sha256,
+    }
+}
+
+fn seeded_third_party_handshake(seed: u64) -> (Vec<u8>, Vec<u8>, [u8; 32]) {
+    let config = config_with(&[third_party_suite()]);
+    let mut client_rng = StdRng::seed_from_u64(seed);
+    let mut server_rng = StdRng::seed_from_u64(seed + 1);
+    let (client_hello, client_state) = generate_client_hello_with_rng_using::<
+        ThirdPartyKem,
+        ThirdPartyDh,
+        _,
+    >(&config, &mut client_rng);
+    let client_hello_bytes = client_hello.to_bytes();
+    let (server_hello, server_session) = handle_client_hello_with_rng_using::<
+        ThirdPartyKem,
+        ThirdPartyDh,
+        _,
+    >(client_hello, &config, &mut server_rng)
+    .unwrap();
+    let server_hello_bytes = server_hello.to_bytes();
+    let client_session =
+        handle_server_hello_with::<ThirdPartyKem, ThirdPartyDh>(server_hello, client_state)
+            .unwrap();
+    assert_eq!(client_session.transcript, server_session.transcript);
+    assert_eq!(client_session.keys.k_client_to_server, server_session.keys.k_client_to_server);
+    (client_hello_bytes, server_hello_bytes, client_session.keys.k_client_to_server)
+}
+
+#[test]
+fn test_third_party_primitives_negotiate() {
+    let config = config_with(&[third_party_suite()]);
+    let (client_hello, client_state) =
+        generate_client_hello_with::<ThirdPartyKem, ThirdPartyDh>(&config);
+    assert_eq!(client_hello.kem_shares[0].kem, KemAlgorithm(0xfe00));
+    assert_eq!(client_hello.dh_shares[0].group, ClassicalGroup(0xfe00));
+
+    let (server_hello, mut server_session) =
+        handle_client_hello_with::<ThirdPartyKem, ThirdPartyDh>(client_hello.clone(), &config)
+            .unwrap();
+    assert_eq!(server_hello.cipher_suite, third_party_suite());
+    let mut client_session =
+        handle_server_hello_with::<ThirdPartyKem, ThirdPartyDh>(server_hello, client_state)
+            .unwrap();
+    client_session.verify_finished(&server_session.finished()).unwrap();
+    server_session.verify_finished(&client_session.finished()).unwrap();
+
+    // The built-in dispatch does not know the code point
+    assert!(matches!(
+        handle_client_hello(client_hello, &config),
+        Err(HandshakeError::NoCommonSuite)
+    ));
+
+    // Sessions over caller-supplied primitives carry records but cannot re-key in band
+    let mut client = SecureChannel::new(client_session).unwrap();
+    let mut server = SecureChannel::new(server_session).unwrap();
+    let record = client.encrypt(b"hello").remove(0);
+    assert_eq!(server.decrypt(&record).unwrap(), Incoming::Data(b"hello".to_vec()));
+    assert!(matches!(client.rekey(), Err(ChannelError::RekeyUnsupported)));
+}
+
+#[test]
+fn test_seeded_third_party_handshake_is_reproducible() {
+    let first = seeded_third_party_handshake(42);
+    assert_eq!(first, seeded_third_party_handshake(42));
+    assert_ne!(first, seeded_third_party_handshake(43));
+}
+
+#[test]
+fn test_server_accepts_baseline_v1_client() {
+    // A ClientHello exactly as the original v1 client encodes it: version, varint-prefixed
+    // Kyber768 key, X25519 key, in a bare frame
+    let (kyber_pk, kyber_sk) = Kyber768Kem::generate_keypair();
+    let (x25519_pk, x25519_sk) = X25519Dh::generate_keypair();
+    let mut hello_bytes = vec![1, 0xa0, 0x09];
+    hello_bytes.extend_from_slice(&Kyber768Kem::public_key_to_bytes(&kyber_pk));
+    hello_bytes.extend_from_slice(&X25519Dh::public_key_to_bytes(&x25519_pk));
+
+    let config = HandshakeConfig::default();
+    let client_hello = LegacyClientHello::from_bytes(&hello_bytes).unwrap();
+    assert_eq!(client_hello.to_bytes(), hello_bytes);
+    let (server_hello, session) = handle_legacy_client_hello(client_hello, &config).unwrap();
+
+    // The v1 client's side: decapsulate, hash both hellos, derive as it always has
+    let server_hello_bytes = server_hello.to_bytes();
+    let server_hello = LegacyServerHello::from_bytes(&server_hello_bytes).unwrap();
+    let kyber_ct = Kyber768Kem::ciphertext_from_bytes(&server_hello.kyber_ct).unwrap();
+    let ss_pq = Kyber768Kem::decapsulate(&kyber_sk, &kyber_ct).unwrap();
+    let server_x25519_pk = X25519Dh::public_key_from_bytes(&server_hello.x25519_pk).unwrap();
+    let ss_classical = X25519Dh::diffie_hellman(&x25519_sk, &server_x25519_pk);
+    let transcript: [u8; 32] = HashAlgorithm::Sha256
+        .digest(&[hello_bytes, server_hello_bytes].concat())
+        .try_into()
+        .unwrap();
+    let keys = derive_session_keys(&ss_pq, &ss_classical, &transcript);
+    assert_eq!(session.transcript, transcript);
+    assert_eq!(session.keys.k_client_to_server, keys.k_client_to_server);
+    assert_eq!(session.keys.nonce_base_s2c, keys.nonce_base_s2c);
+
+    // Application data both ways, with `seq ‖ transcript` as additional data
+    let mut channel = LegacyChannel::new(session);
+    let aad = [&1u64.to_be_bytes()[..], &transcript].concat();
+    let ciphertext = ChaCha20Poly1305Aead::encrypt(
+        &keys.k_client_to_server,
+        &keys.nonce_base_c2s,
+        1,
+        &aad,
+        b"hello",
+    );
+    let frame = LegacyAppData { seq: 1, ciphertext }.to_bytes();
+    let app_data = LegacyAppData::from_bytes(&frame).unwrap();
+    assert_eq!(channel.decrypt(&app_data).unwrap(), b"hello");
+    assert!(matches!(channel.decrypt(&app_data), Err(ChannelError::ReplayDetected)));
+
+    let reply = channel.encrypt(b"hello back");
+    assert_eq!(reply.seq, 1);
+    let plaintext = ChaCha20Poly1305Aead::decrypt(
+        &keys.k_server_to_client,
+        &keys.nonce_base_s2c,
+        reply.seq,
+        &aad,
+        &reply.ciphertext,
+    )
+    .unwrap();
+    assert_eq!(plaintext, b"hello back");
+}
+
+#[test]
+fn test_legacy_client_needs_v1_and_its_suite() {
+    let (kyber_pk, _) = Kyber768Kem::generate_keypair();
+    let (x25519_pk, _) = X25519Dh::generate_keypair();
+    let client_hello = LegacyClientHello {
+        version: PROTOCOL_V1,
+        kyber_pk: Kyber768Kem::public_key_to_bytes(&kyber_pk),
+        x25519_pk: X25519Dh::public_key_to_bytes(&x25519_pk).try_into().unwrap(),
+    };
+
+    let v2_only = versions(&[PROTOCOL_V2]);
+    assert!(matches!(
+        handle_legacy_client_hello(client_hello.clone(), &v2_only),
+        Err(HandshakeError::NoCommonVersion)
+    ));
+    assert!(matches!(
+        handle_legacy_client_hello(client_hello.clone(), &HandshakeConfig::high_security()),
+        Err(HandshakeError::NoCommonVersion)
+    ));
+    let mlkem_only = config_with(&[CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256]);
+    assert!(matches!(
+        handle_legacy_client_hello(client_hello.clone(), &mlkem_only),
+        Err(HandshakeError::NoCommonSuite)
+    ));
+
+    let mut short = client_hello;
+    short.kyber_pk.truncate(1000);
+    assert!(matches!(
+        handle_legacy_client_hello(short, &HandshakeConfig::default()),
+        Err(HandshakeError::InvalidKeySize)
+    ));
 }
@@ -0,0 +1,212 @@
+//! Regression vectors for complete handshakes, driven by `tests/vectors/handshake.json`.
+//!
+//! There are no published vectors for this protocol, so the file records this crate's own
+//! output: it catches unintended wire or key schedule changes, not deviations from a standard.
+//! Both sides draw from ChaCha20Rng, which unlike StdRng has a stable output stream. After an
+//! intentional wire or key schedule change, refresh the file with
+//! `cargo test -p hybrid-kyber-protocol --test kat -- --ignored`.
+
+use std::fs;
+use std::path::PathBuf;
+
+use hybrid_kyber_protocol::handshake::{
+    generate_client_hello_with_rng, handle_client_hello_with_rng, handle_server_hello,
+    HandshakeConfig,
+};
+use hybrid_kyber_protocol::messages::{
+    AeadAlgorithm, CipherSuite, ClassicalGroup, KdfHash, KemAlgorithm,
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+
+const EXPORTER_LABEL: &[u8] = b"EXPORTER-kat";
+const EXPORTER_CONTEXT: &[u8] = b"context";
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct HandshakeVector {
+    name: String,
+    /// Suites both sides are configured with, most preferred first
+    cipher_suites: Vec<CipherSuite>,
+    high_security: bool,
+    /// Application context both sides are configured with
+    context: String,
+    client_seed: String,
+    server_seed: String,
+    client_hello: String,
+    server_hello: String,
+    transcript: String,
+    k_client_to_server: String,
+    k_server_to_client: String,
+    nonce_base_c2s: String,
+    nonce_base_s2c: String,
+    client_finished: String,
+    server_finished: String,
+    channel_binding: String,
+    /// 32 bytes exported under `EXPORTER-kat` with context `context`
+    exported: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct HandshakeFile {
+    description: String,
+    vectors: Vec<HandshakeVector>,
+}
+
+fn vectors_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/vectors/handshake.json")
+}
+
+/// Run one seeded handshake to completion and record everything either side derives
+fn run_handshake(
+    name: &str,
+    cipher_suites: &[CipherSuite],
+    high_security: bool,
+    context: &[u8],
+    client_seed: [u8; 32],
+    server_seed: [u8; 32],
+) -> HandshakeVector {
+    let config = HandshakeConfig {
+        cipher_suites: cipher_suites.to_vec(),
+        context: context.to_vec(),
+        ..if high_security {
+            HandshakeConfig::high_security()
+        } else {
+            HandshakeConfig::default()
+        }
+    };
+    let mut client_rng = ChaCha20Rng::from_seed(client_seed);
+    let mut server_rng = ChaCha20Rng::from_seed(server_seed);
+
+    let (client_hello, client_state) = generate_client_hello_with_rng(&config, &mut client_rng);
+    let client_hello_bytes = client_hello.to_bytes();
+    let (server_hello, mut server) =
+        handle_client_hello_with_rng(client_hello, &config, &mut server_rng).unwrap();
+    let server_hello_bytes = server_hello.to_bytes();
+    let mut client = handle_server_hello(server_hello, client_state).unwrap();
+
+    let client_finished = client.finished();
+    let server_finished = server.finished();
+    server.verify_finished(&client_finished).unwrap();
+    client.verify_finished(&server_finished).unwrap();
+
+    assert_eq!(client.transcript, server.transcript);
+    assert_eq!(client.channel_binding(), server.channel_binding());
+    let exported = client.export_keying_material(EXPORTER_LABEL, EXPORTER_CONTEXT, 32).unwrap();
+    assert_eq!(
+        exported,
+        server.export_keying_material(EXPORTER_LABEL, EXPORTER_CONTEXT, 32).unwrap()
+    );
+    assert_eq!(client.keys.k_client_to_server, server.keys.k_client_to_server);
+    assert_eq!(client.keys.k_server_to_client, server.keys.k_server_to_client);
+
+    HandshakeVector {
+        name: name.to_string(),
+        cipher_suites: cipher_suites.to_vec(),
+        high_security,
+        context: hex::encode(context),
+        client_seed: hex::encode(client_seed),
+        server_seed: hex::encode(server_seed),
+        client_hello: hex::encode(client_hello_bytes),
+        server_hello: hex::encode(server_hello_bytes),
+        transcript: hex::encode(&client.transcript),
+        k_client_to_server: hex::encode(client.keys.k_client_to_server),
+        k_server_to_client: hex::encode(client.keys.k_server_to_client),
+        nonce_base_c2s: hex::encode(client.keys.nonce_base_c2s),
+        nonce_base_s2c: hex::encode(client.keys.nonce_base_s2c),
+        client_finished: hex::encode(client_finished.to_bytes()),
+        server_finished: hex::encode(server_finished.to_bytes()),
+        channel_binding: hex::encode(client.channel_binding()),
+        exported: hex::encode(&*exported),
+    }
+}
+
+fn seed(hex_seed: &str) -> [u8; 32] {
+    hex::decode(hex_seed).unwrap().try_into().unwrap()
+}
+
+#[test]
+fn test_handshake_vectors() {
+    let json = fs::read_to_string(vectors_path()).unwrap();
+    let file: HandshakeFile = serde_json::from_str(&json).unwrap();
+    assert!(!file.vectors.is_empty());
+    for v in &file.vectors {
+        let actual = run_handshake(
+            &v.name,
+            &v.cipher_suites,
+            v.high_security,
+            &hex::decode(&v.context).unwrap(),
+            seed(&v.client_seed),
+            seed(&v.server_seed),
+        );
+        assert_eq!(&actual, v, "{}", v.name);
+    }
+}
+
+#[test]
+#[ignore = "rewrites tests/vectors/handshake.json"]
+fn regenerate_handshake_vectors() {
+    let kyber1024_x448 = CipherSuite {
+        kem: KemAlgorithm::KYBER1024,
+        group: ClassicalGroup::X448,
+        aead: AeadAlgorithm::Aes256Gcm,
+        kdf: KdfHash::Sha256,
+    };
+    let cases: [(&str, &[CipherSuite], bool, &[u8]); 7] = [
+        (
+            "mlkem768-x25519-chacha20poly1305-sha256",
+            &[CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256],
+            false,
+            b"",
+        ),
+        (
+            "mlkem768-p256-aes256gcm-sha256",
+            &[CipherSuite::MLKEM768_P256_AES256GCM_SHA256],
+            false,
+            b"",
+        ),
+        (
+            "mlkem768-x448-chacha20poly1305-sha256",
+            &[CipherSuite::MLKEM768_X448_CHACHA20POLY1305_SHA256],
+            false,
+            b"",
+        ),
+        (
+            "high-security-mlkem1024-x448-aes256gcm-sha384",
+            &[CipherSuite::MLKEM1024_X448_AES256GCM_SHA384],
+            true,
+            b"",
+        ),
+        (
+            "mlkem768-x25519-chacha20poly1305-sha256-context",
+            &[CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256],
+            false,
+            b"kat-service",
+        ),
+        (
+            "kyber768-x25519-chacha20poly1305-sha256",
+            &[CipherSuite::KYBER768_X25519_CHACHA20POLY1305_SHA256],
+            false,
+            b"",
+        ),
+        ("kyber1024-x448-aes256gcm-sha256", &[kyber1024_x448], false, b""),
+    ];
+    let vectors = cases
+        .iter()
+        .enumerate()
+        .map(|(i, &(name, suites, high_security, context))| {
+            let client_seed = [2 * i as u8; 32];
+            let server_seed = [2 * i as u8 + 1; 32];
+            run_handshake(name, suites, high_security, context, client_seed, server_seed)
+        })
+        .collect();
+    let file = HandshakeFile {
+        description: "Self-regression vectors, not known answers: complete handshakes as this \
+                      crate runs them, with each side's randomness drawn from ChaCha20Rng seeded \
+                      with client_seed / server_seed. Messages are hex-encoded wire bytes."
+            .to_string(),
+        vectors,
+    };
+    fs::create_dir_all(vectors_path().parent().unwrap()).unwrap();
+    fs::write(vectors_path(), serde_json::to_string_pretty(&file).unwrap() + "\n").unwrap();
+}
@@ -1,45 +1,115 @@
-use hybrid_kyber_protocol::messages::{AppData, ClientHello, ServerHello};
+use hybrid_kyber_protocol::messages::{
+    find_extension, CipherSuite, ClassicalGroup, ClientHello, DhShare, Extension,
+    HelloExtension, KemAlgorithm, KemShare, MessageError, ServerHello,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct ServiceName(String);
+
+impl HelloExtension for ServiceName {
+    const TYPE: u16 = 0x7F01;
+}
 
 #[test]
 fn test_client_hello_roundtrip() {
     let msg = ClientHello {
-        version: 1,
-        kyber_pk: vec![0xAA; 1184],
-        x25519_pk: [0xBB; 32],
+        versions: vec![2, 1],
+        cipher_suites: vec![
+            CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256,
+            CipherSuite::KYBER768_X25519_CHACHA20POLY1305_SHA256,
+        ],
+        kem_shares: vec![KemShare {
+            kem: KemAlgorithm::MLKEM768,
+            public_key: vec![0xAA; 1184],
+        }],
+        dh_shares: vec![DhShare {
+            group: ClassicalGroup::X25519,
+            public_key: vec![0xBB; 32],
+        }],
+        extensions: vec![],
     };
 
     let bytes = msg.to_bytes();
     let recovered = ClientHello::from_bytes(&bytes).unwrap();
 
-    assert_eq!(recovered.version, 1);
-    assert_eq!(recovered.kyber_pk.len(), 1184);
-    assert_eq!(recovered.x25519_pk, [0xBB; 32]);
+    assert_eq!(recovered.versions, vec![2, 1]);
+    assert_eq!(recovered.cipher_suites, msg.cipher_suites);
+    assert_eq!(recovered.kem_share(KemAlgorithm::MLKEM768).unwrap().public_key.len(), 1184);
+    assert!(recovered.kem_share(KemAlgorithm::KYBER768).is_none());
+    assert_eq!(
+        recovered.dh_share(ClassicalGroup::X25519).unwrap().public_key,
+        vec![0xBB; 32]
+    );
+    assert!(recovered.dh_share(ClassicalGroup::P256).is_none());
 }
 
 #[test]
 fn test_server_hello_roundtrip() {
     let msg = ServerHello {
+        version: 2,
+        cipher_suite: CipherSuite::MLKEM768_X25519_AES256GCM_SHA256,
         kyber_ct: vec![0xCC; 1088],
-        x25519_pk: [0xDD; 32],
+        dh_public_key: vec![0xDD; 32],
+        extensions: vec![],
     };
 
     let bytes = msg.to_bytes();
     let recovered = ServerHello::from_bytes(&bytes).unwrap();
 
+    assert_eq!(recovered.version, 2);
+    assert_eq!(recovered.cipher_suite, CipherSuite::MLKEM768_X25519_AES256GCM_SHA256);
     assert_eq!(recovered.kyber_ct.len(), 1088);
-    assert_eq!(recovered.x25519_pk, [0xDD; 32]);
+    assert_eq!(recovered.dh_public_key, vec![0xDD; 32]);
 }
 
 #[test]
-fn test_app_data_roundtrip() {
-    let msg = AppData {
-        seq: 42,
-        ciphertext: vec![0xEE; 100],
-    };
+fn test_known_extension_roundtrip() {
+    let extensions = vec![
+        Extension {
+            extension_type: 0xFFFF,
+            data: vec![1, 2, 3],
+        },
+        ServiceName("chat".into()).to_extension(),
+    ];
 
-    let bytes = msg.to_bytes();
-    let recovered = AppData::from_bytes(&bytes).unwrap();
+    let found: Option<ServiceName> = find_extension(&extensions).unwrap();
+    assert_eq!(found, Some(ServiceName("chat".into())));
+
+    let missing: Option<ServiceName> = find_extension(&extensions[..1]).unwrap();
+    assert!(missing.is_none());
+}
+
+#[test]
+fn test_malformed_known_extension_rejected() {
+    let extensions = vec![Extension {
+        extension_type: ServiceName::TYPE,
+        data: vec![0xFF],
+    }];
+
+    let result: Result<Option<ServiceName>, _> = find_extension(&extensions);
+    assert!(matches!(result, Err(MessageError::InvalidExtension(0x7F01, _))));
+}
+
+#[test]
+fn test_duplicate_extensions_rejected() {
+    let msg = ServerHello {
+        version: 2,
+        cipher_suite: CipherSuite::MLKEM768_X25519_AES256GCM_SHA256,
+        kyber_ct: vec![0xCC; 1088],
+        dh_public_key: vec![0xDD; 32],
+        extensions: vec![
+            Extension {
+                extension_type: 0x1234,
+                data: vec![1],
+            },
+            Extension {
+                extension_type: 0x1234,
+                data: vec![2],
+            },
+        ],
+    };
 
-    assert_eq!(recovered.seq, 42);
-    assert_eq!(recovered.ciphertext.len(), 100);
+    let result = ServerHello::from_bytes(&msg.to_bytes());
+    assert!(matches!(result, Err(MessageError::DuplicateExtension(0x1234))));
 }
@@ -0,0 +1,41 @@
+use hybrid_kyber_protocol::handshake::HandshakeError;
+use hybrid_kyber_protocol::messages::{Alert, AlertDescription};
+use hybrid_kyber_protocol::record::{handshake_message, ContentType, Record};
+
+#[test]
+fn test_record_roundtrip() {
+    let record = Record {
+        content_type: ContentType::ApplicationData,
+        seq: Some(42),
+        payload: vec![0xEE; 100],
+    };
+
+    let bytes = record.to_bytes();
+    assert_eq!(bytes[0], 23);
+    let recovered = Record::from_bytes(&bytes).unwrap();
+    assert_eq!(recovered, record);
+
+    let implicit = Record::handshake(vec![1, 2, 3]);
+    assert_eq!(implicit.to_bytes(), vec![22, 0, 3, 1, 2, 3]);
+}
+
+#[test]
+fn test_unknown_content_type_rejected() {
+    let mut bytes = Record::handshake(vec![1]).to_bytes();
+    bytes[0] = 99;
+    assert!(Record::from_bytes(&bytes).is_err());
+}
+
+#[test]
+fn test_handshake_message_routing() {
+    assert_eq!(handshake_message(Record::handshake(vec![7])).unwrap(), vec![7]);
+
+    let alert = Record::alert(Alert::new(AlertDescription::ProtocolVersion));
+    assert!(matches!(
+        handshake_message(alert),
+        Err(HandshakeError::AlertReceived(AlertDescription::ProtocolVersion))
+    ));
+
+    let data = Record::plaintext(ContentType::ApplicationData, vec![7]);
+    assert!(matches!(handshake_message(data), Err(HandshakeError::UnexpectedMessage)));
+}
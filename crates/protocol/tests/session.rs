@@ -1,34 +1,55 @@
-use hybrid_kyber_protocol::handshake::{generate_client_hello, handle_client_hello, handle_server_hello};
-use hybrid_kyber_protocol::session::{ChannelError, SecureChannel};
+use crypto::hkdf::ExportError;
+use hybrid_kyber_protocol::handshake::{
+    generate_client_hello, generate_client_hello_with_rng, handle_client_hello,
+    handle_client_hello_with_rng, handle_server_hello, HandshakeConfig, HandshakeError,
+};
+use hybrid_kyber_protocol::messages::{AlertDescription, CipherSuite};
+use hybrid_kyber_protocol::record::{ContentType, Record};
+use hybrid_kyber_protocol::session::{ChannelConfig, ChannelError, Incoming, SecureChannel};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
-fn create_channel_pair() -> (SecureChannel, SecureChannel) {
-    let (client_hello, client_state) = generate_client_hello();
-    let (server_hello, server_session) = handle_client_hello(client_hello).unwrap();
-    let client_session = handle_server_hello(server_hello, client_state).unwrap();
+fn create_channel_pair_with(
+    cipher_suite: CipherSuite,
+    channel_config: ChannelConfig,
+) -> (SecureChannel, SecureChannel) {
+    let config = HandshakeConfig {
+        cipher_suites: vec![cipher_suite],
+        ..HandshakeConfig::default()
+    };
+    let (client_hello, client_state) = generate_client_hello(&config);
+    let (server_hello, server_session) = handle_client_hello(client_hello, &config).unwrap();
+    let mut server_session = server_session;
+    let mut client_session = handle_server_hello(server_hello, client_state).unwrap();
 
-    let client_channel = SecureChannel::new(
-        client_session.keys,
-        client_session.transcript,
-        true,
-    );
-    let server_channel = SecureChannel::new(
-        server_session.keys,
-        server_session.transcript,
-        false,
-    );
+    client_session
+        .verify_finished(&server_session.finished())
+        .unwrap();
+    server_session
+        .verify_finished(&client_session.finished())
+        .unwrap();
+
+    let client_channel = SecureChannel::with_config(client_session, channel_config.clone()).unwrap();
+    let server_channel = SecureChannel::with_config(server_session, channel_config).unwrap();
 
     (client_channel, server_channel)
 }
 
+fn create_channel_pair() -> (SecureChannel, SecureChannel) {
+    create_channel_pair_with(
+        CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256,
+        ChannelConfig::default(),
+    )
+}
 #[test]
 fn test_client_to_server() {
     let (mut client, mut server) = create_channel_pair();
 
     let plaintext = b"hello server!";
-    let encrypted = client.encrypt(plaintext);
+    let encrypted = client.encrypt(plaintext).remove(0);
     let decrypted = server.decrypt(&encrypted).unwrap();
 
-    assert_eq!(decrypted, plaintext);
+    assert_eq!(decrypted, Incoming::Data(plaintext.to_vec()));
 }
 
 #[test]
@@ -36,17 +57,28 @@ fn test_server_to_client() {
     let (mut client, mut server) = create_channel_pair();
 
     let plaintext = b"hello client!";
-    let encrypted = server.encrypt(plaintext);
+    let encrypted = server.encrypt(plaintext).remove(0);
     let decrypted = client.decrypt(&encrypted).unwrap();
 
-    assert_eq!(decrypted, plaintext);
+    assert_eq!(decrypted, Incoming::Data(plaintext.to_vec()));
+}
+
+fn explicit_sequence_numbers() -> ChannelConfig {
+    ChannelConfig {
+        explicit_sequence_numbers: true,
+        ..ChannelConfig::default()
+    }
 }
 
 #[test]
 fn test_replay_rejected() {
-    let (mut client, mut server) = create_channel_pair();
+    let (mut client, mut server) = create_channel_pair_with(
+        CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256,
+        explicit_sequence_numbers(),
+    );
 
-    let encrypted = client.encrypt(b"message 1");
+    let encrypted = client.encrypt(b"message 1").remove(0);
+    assert_eq!(encrypted.seq, Some(1));
     server.decrypt(&encrypted).unwrap();
 
     let result = server.decrypt(&encrypted);
@@ -55,13 +87,492 @@ fn test_replay_rejected() {
 
 #[test]
 fn test_out_of_order_rejected() {
-    let (mut client, mut server) = create_channel_pair();
+    let (mut client, mut server) = create_channel_pair_with(
+        CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256,
+        explicit_sequence_numbers(),
+    );
 
-    let msg1 = client.encrypt(b"message 1");
-    let msg2 = client.encrypt(b"message 2");
+    let msg1 = client.encrypt(b"message 1").remove(0);
+    let msg2 = client.encrypt(b"message 2").remove(0);
 
     server.decrypt(&msg2).unwrap();
 
     let result = server.decrypt(&msg1);
     assert!(matches!(result, Err(ChannelError::ReplayDetected)));
 }
+
+#[test]
+fn test_aes256gcm_channel() {
+    let (mut client, mut server) = create_channel_pair_with(
+        CipherSuite::MLKEM768_X25519_AES256GCM_SHA256,
+        ChannelConfig::default(),
+    );
+
+    let encrypted = client.encrypt(b"hello over AES-GCM").remove(0);
+    assert_eq!(
+        server.decrypt(&encrypted).unwrap(),
+        Incoming::Data(b"hello over AES-GCM".to_vec())
+    );
+
+    let encrypted = server.encrypt(b"and back").remove(0);
+    assert_eq!(client.decrypt(&encrypted).unwrap(), Incoming::Data(b"and back".to_vec()));
+}
+
+#[test]
+fn test_channel_requires_verified_finished() {
+    let config = HandshakeConfig::default();
+    let (client_hello, client_state) = generate_client_hello(&config);
+    let (server_hello, _) = handle_client_hello(client_hello, &config).unwrap();
+    let client_session = handle_server_hello(server_hello, client_state).unwrap();
+
+    assert!(matches!(
+        SecureChannel::new(client_session),
+        Err(HandshakeError::FinishedNotVerified)
+    ));
+}
+
+#[test]
+fn test_exporter_matches_on_both_sides() {
+    let (client, server) = create_channel_pair();
+
+    let client_ekm = client.export_keying_material(b"app auth", b"ctx", 32).unwrap();
+    let server_ekm = server.export_keying_material(b"app auth", b"ctx", 32).unwrap();
+    assert_eq!(client_ekm, server_ekm);
+    assert_eq!(client.channel_binding(), server.channel_binding());
+
+    assert_ne!(client_ekm, client.export_keying_material(b"other", b"ctx", 32).unwrap());
+    assert_ne!(client_ekm, client.export_keying_material(b"app auth", b"other", 32).unwrap());
+    assert_eq!(client.export_keying_material(b"app auth", b"ctx", 64).unwrap().len(), 64);
+}
+
+#[test]
+fn test_exporter_rejects_oversized_requests() {
+    let (client, _) = create_channel_pair_with(
+        CipherSuite::MLKEM1024_X448_AES256GCM_SHA384,
+        ChannelConfig::default(),
+    );
+
+    assert_eq!(client.export_keying_material(b"app auth", b"", 255 * 48).unwrap().len(), 12240);
+    assert_eq!(
+        client.export_keying_material(b"app auth", b"", 255 * 48 + 1).unwrap_err(),
+        ExportError::LengthTooLong
+    );
+    assert_eq!(
+        client.export_keying_material(&[0u8; 251], b"", 32).unwrap_err(),
+        ExportError::LabelTooLong
+    );
+}
+
+#[test]
+fn test_exporter_and_binding_differ_per_session() {
+    let (client_a, _) = create_channel_pair();
+    let (client_b, _) = create_channel_pair();
+
+    assert_ne!(
+        client_a.export_keying_material(b"app auth", b"", 32).unwrap(),
+        client_b.export_keying_material(b"app auth", b"", 32).unwrap()
+    );
+    assert_ne!(client_a.channel_binding(), client_b.channel_binding());
+}
+
+#[test]
+fn test_explicit_key_update() {
+    let (mut client, mut server) = create_channel_pair();
+
+    let before = client.encrypt(b"old keys").remove(0);
+    let update = client.update_keys();
+    let after = client.encrypt(b"new keys").remove(0);
+
+    assert_eq!(server.decrypt(&before).unwrap(), Incoming::Data(b"old keys".to_vec()));
+    assert_eq!(server.decrypt(&update).unwrap(), Incoming::KeyUpdate);
+    assert_eq!(server.decrypt(&after).unwrap(), Incoming::Data(b"new keys".to_vec()));
+
+    // The other direction is untouched
+    let reply = server.encrypt(b"reply").remove(0);
+    assert_eq!(client.decrypt(&reply).unwrap(), Incoming::Data(b"reply".to_vec()));
+}
+
+#[test]
+fn test_record_after_key_update_needs_new_keys() {
+    let (mut client, mut server) = create_channel_pair();
+
+    let _dropped_update = client.update_keys();
+    let after = client.encrypt(b"new keys").remove(0);
+
+    assert!(matches!(server.decrypt(&after), Err(ChannelError::DecryptionFailed(_))));
+}
+
+#[test]
+fn test_automatic_key_update_by_record_count() {
+    let config = ChannelConfig {
+        key_update_after_records: Some(3),
+        key_update_after_bytes: None,
+        rekey_after_records: None,
+        ..ChannelConfig::default()
+    };
+    let (mut client, mut server) =
+        create_channel_pair_with(CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256, config);
+
+    let mut updates = 0;
+    for i in 0..10u8 {
+        for record in client.encrypt(&[i]) {
+            match server.decrypt(&record).unwrap() {
+                Incoming::Data(data) => assert_eq!(data, vec![i]),
+                Incoming::KeyUpdate => updates += 1,
+                Incoming::Rekey { .. } | Incoming::CloseNotify => panic!("unexpected record"),
+            }
+        }
+    }
+    assert_eq!(updates, 3);
+}
+
+#[test]
+fn test_automatic_key_update_by_byte_count() {
+    let config = ChannelConfig {
+        key_update_after_records: None,
+        key_update_after_bytes: Some(100),
+        rekey_after_records: None,
+        ..ChannelConfig::default()
+    };
+    let (mut client, mut server) =
+        create_channel_pair_with(CipherSuite::MLKEM768_X25519_AES256GCM_SHA256, config);
+
+    let first = client.encrypt(&[0u8; 60]);
+    assert_eq!(first.len(), 1);
+    let second = client.encrypt(&[0u8; 60]);
+    assert_eq!(second.len(), 2);
+    let third = client.encrypt(&[0u8; 60]);
+    assert_eq!(third.len(), 1);
+
+    let received: Vec<Incoming> = first
+        .iter()
+        .chain(&second)
+        .chain(&third)
+        .map(|record| server.decrypt(record).unwrap())
+        .collect();
+    assert_eq!(received[2], Incoming::KeyUpdate);
+    assert_eq!(received[3], Incoming::Data(vec![0u8; 60]));
+}
+
+fn reply(incoming: Incoming) -> Vec<Record> {
+    match incoming {
+        Incoming::Rekey { reply } => reply,
+        other => panic!("expected a re-key step, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_rekey_keeps_in_flight_records() {
+    let (mut client, mut server) = create_channel_pair();
+
+    let request = client.rekey().unwrap();
+    let client_old = client.encrypt(b"client before switch").remove(0);
+    let server_old = server.encrypt(b"server before switch").remove(0);
+
+    let mut response = reply(server.decrypt(&request).unwrap());
+    assert_eq!(response.len(), 1);
+    let server_new = server.encrypt(b"server after switch").remove(0);
+
+    assert_eq!(
+        server.decrypt(&client_old).unwrap(),
+        Incoming::Data(b"client before switch".to_vec())
+    );
+    assert_eq!(
+        client.decrypt(&server_old).unwrap(),
+        Incoming::Data(b"server before switch".to_vec())
+    );
+
+    let mut finished = reply(client.decrypt(&response.remove(0)).unwrap());
+    assert_eq!(finished.len(), 1);
+    let client_new = client.encrypt(b"client after switch").remove(0);
+
+    assert_eq!(
+        client.decrypt(&server_new).unwrap(),
+        Incoming::Data(b"server after switch".to_vec())
+    );
+    assert!(reply(server.decrypt(&finished.remove(0)).unwrap()).is_empty());
+    assert_eq!(
+        server.decrypt(&client_new).unwrap(),
+        Incoming::Data(b"client after switch".to_vec())
+    );
+}
+
+#[test]
+fn test_rekey_stays_in_negotiated_group() {
+    for suite in [
+        CipherSuite::MLKEM768_P256_AES256GCM_SHA256,
+        CipherSuite::MLKEM768_X448_CHACHA20POLY1305_SHA256,
+        CipherSuite::MLKEM1024_X448_CHACHA20POLY1305_SHA384,
+    ] {
+        let (mut client, mut server) = create_channel_pair_with(suite, ChannelConfig::default());
+
+        let request = client.rekey().unwrap();
+        let response = reply(server.decrypt(&request).unwrap()).remove(0);
+        let finished = reply(client.decrypt(&response).unwrap()).remove(0);
+        assert!(reply(server.decrypt(&finished).unwrap()).is_empty());
+
+        let record = client.encrypt(b"after rekey").remove(0);
+        assert_eq!(
+            server.decrypt(&record).unwrap(),
+            Incoming::Data(b"after rekey".to_vec())
+        );
+    }
+}
+
+#[test]
+fn test_rekey_changes_keys() {
+    let (mut client, mut server) = create_channel_pair();
+
+    let request = client.rekey().unwrap();
+    let response = reply(server.decrypt(&request).unwrap()).remove(0);
+    let _lost_finished = reply(client.decrypt(&response).unwrap());
+
+    // Without the Finished record the server still expects the pre-rekey client key
+    let after = client.encrypt(b"new keys").remove(0);
+    assert!(matches!(server.decrypt(&after), Err(ChannelError::DecryptionFailed(_))));
+}
+
+#[test]
+fn test_simultaneous_rekey_resolves_to_client() {
+    let (mut client, mut server) = create_channel_pair();
+
+    let client_request = client.rekey().unwrap();
+    let server_request = server.rekey().unwrap();
+
+    assert!(reply(client.decrypt(&server_request).unwrap()).is_empty());
+    let response = reply(server.decrypt(&client_request).unwrap()).remove(0);
+    let finished = reply(client.decrypt(&response).unwrap()).remove(0);
+    assert!(reply(server.decrypt(&finished).unwrap()).is_empty());
+
+    let data = client.encrypt(b"settled").remove(0);
+    assert_eq!(server.decrypt(&data).unwrap(), Incoming::Data(b"settled".to_vec()));
+    let data = server.encrypt(b"settled too").remove(0);
+    assert_eq!(client.decrypt(&data).unwrap(), Incoming::Data(b"settled too".to_vec()));
+    assert!(server.rekey().is_ok());
+}
+
+#[test]
+fn test_second_rekey_while_pending_rejected() {
+    let (mut client, _) = create_channel_pair();
+
+    client.rekey().unwrap();
+    assert!(matches!(client.rekey(), Err(ChannelError::RekeyInProgress)));
+}
+
+#[test]
+fn test_unsolicited_rekey_response_rejected() {
+    let (mut client, mut server) = create_channel_pair();
+
+    let request = client.rekey().unwrap();
+    let response = reply(server.decrypt(&request).unwrap()).remove(0);
+    client.decrypt(&response).unwrap();
+
+    // A replay is taken under the next sequence number and no longer authenticates
+    assert!(client.decrypt(&response).is_err());
+}
+
+#[test]
+fn test_automatic_rekey() {
+    let config = ChannelConfig {
+        key_update_after_records: None,
+        key_update_after_bytes: None,
+        rekey_after_records: Some(4),
+        ..ChannelConfig::default()
+    };
+    let (mut client, mut server) =
+        create_channel_pair_with(CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256, config);
+
+    let mut rekey_steps = 0;
+    for i in 0..20u8 {
+        let mut to_server = client.encrypt(&[i]);
+        while !to_server.is_empty() {
+            let mut to_client = Vec::new();
+            for record in to_server.drain(..) {
+                match server.decrypt(&record).unwrap() {
+                    Incoming::Data(data) => {
+                        assert_eq!(data, vec![i]);
+                        to_client.extend(server.encrypt(&data));
+                    }
+                    Incoming::Rekey { reply } => {
+                        rekey_steps += 1;
+                        to_client.extend(reply);
+                    }
+                    Incoming::KeyUpdate | Incoming::CloseNotify => panic!("unexpected record"),
+                }
+            }
+            for record in to_client {
+                match client.decrypt(&record).unwrap() {
+                    Incoming::Data(data) => assert_eq!(data, vec![i]),
+                    Incoming::Rekey { reply } => {
+                        rekey_steps += 1;
+                        to_server.extend(reply);
+                    }
+                    Incoming::KeyUpdate | Incoming::CloseNotify => panic!("unexpected record"),
+                }
+            }
+        }
+    }
+    assert!(rekey_steps >= 6);
+}
+
+#[test]
+fn test_handshake_state_and_channel_debug_are_redacted() {
+    let config = HandshakeConfig::default();
+    let (client_hello, client_state) = generate_client_hello(&config);
+    assert!(format!("{:?}", client_state).ends_with(".. }"));
+    let (_, server_session) = handle_client_hello(client_hello, &config).unwrap();
+    let printed = format!("{:?}", server_session);
+    assert!(!printed.contains("keys"));
+    assert!(!printed.contains("key_schedule"));
+
+    let (client, _server) = create_channel_pair();
+    let printed = format!("{:?}", client);
+    assert!(printed.starts_with("SecureChannel {"));
+    assert!(!printed.contains("exporter_master"));
+    assert!(!printed.contains("traffic_secret"));
+}
+
+#[test]
+fn test_close_notify_ends_stream_cleanly() {
+    let (mut client, mut server) = create_channel_pair();
+    let record = client.encrypt(b"last words").remove(0);
+    assert_eq!(server.decrypt(&record).unwrap(), Incoming::Data(b"last words".to_vec()));
+    assert!(matches!(server.end_of_stream(), Err(ChannelError::Truncated)));
+
+    let close = client.close();
+    assert_eq!(server.decrypt(&close).unwrap(), Incoming::CloseNotify);
+    assert!(server.end_of_stream().is_ok());
+
+    // Nothing the peer sends after its alert is accepted
+    let late = client.encrypt(b"too late").remove(0);
+    assert!(matches!(server.decrypt(&late), Err(ChannelError::Closed)));
+}
+
+#[test]
+fn test_fatal_error_alert_reaches_peer() {
+    let (mut client, mut server) = create_channel_pair();
+    let mut record = client.encrypt(b"hello").remove(0);
+    record.payload[0] ^= 1;
+
+    let error = server.decrypt(&record).unwrap_err();
+    assert_eq!(error.alert(), Some(AlertDescription::BadRecordMac));
+    let alert = server.alert(error.alert().unwrap());
+    assert!(matches!(
+        client.decrypt(&alert),
+        Err(ChannelError::AlertReceived(AlertDescription::BadRecordMac))
+    ));
+    assert!(matches!(
+        client.end_of_stream(),
+        Err(ChannelError::AlertReceived(AlertDescription::BadRecordMac))
+    ));
+}
+
+#[test]
+fn test_alert_is_authenticated() {
+    let (mut client, mut server) = create_channel_pair();
+    let mut close = client.close();
+    close.payload[1] ^= 1;
+    assert!(matches!(server.decrypt(&close), Err(ChannelError::DecryptionFailed(_))));
+    assert!(matches!(server.end_of_stream(), Err(ChannelError::Truncated)));
+}
+
+#[test]
+fn test_implicit_sequence_numbers_reject_replay_and_reordering() {
+    let (mut client, mut server) = create_channel_pair();
+
+    let msg1 = client.encrypt(b"message 1").remove(0);
+    let msg2 = client.encrypt(b"message 2").remove(0);
+    assert_eq!(msg1.seq, None);
+
+    // Out of order, the record is opened under the wrong number
+    assert!(matches!(server.decrypt(&msg2), Err(ChannelError::DecryptionFailed(_))));
+    assert_eq!(server.decrypt(&msg1).unwrap(), Incoming::Data(b"message 1".to_vec()));
+    assert!(matches!(server.decrypt(&msg1), Err(ChannelError::DecryptionFailed(_))));
+}
+
+#[test]
+fn test_sequence_number_mode_must_match() {
+    let (mut client, mut server) = create_channel_pair();
+    let mut record = client.encrypt(b"hello").remove(0);
+    record.seq = Some(1);
+    assert!(matches!(server.decrypt(&record), Err(ChannelError::InvalidSequence)));
+}
+
+#[test]
+fn test_content_type_and_length_are_authenticated() {
+    let (mut client, mut server) = create_channel_pair();
+
+    let mut record = client.encrypt(&[1]).remove(0);
+    assert_eq!(record.content_type, ContentType::ApplicationData);
+    record.content_type = ContentType::Control;
+    assert!(matches!(server.decrypt(&record), Err(ChannelError::DecryptionFailed(_))));
+
+    let (mut client, mut server) = create_channel_pair();
+    let mut record = client.encrypt(b"hello").remove(0);
+    record.payload.push(0);
+    assert!(matches!(server.decrypt(&record), Err(ChannelError::DecryptionFailed(_))));
+}
+
+#[test]
+fn test_records_dispatch_by_content_type() {
+    let (mut client, mut server) = create_channel_pair();
+
+    let update = client.update_keys();
+    assert_eq!(update.content_type, ContentType::Control);
+    assert_eq!(server.decrypt(&update).unwrap(), Incoming::KeyUpdate);
+
+    let request = client.rekey().unwrap();
+    assert_eq!(request.content_type, ContentType::Control);
+    assert!(matches!(server.decrypt(&request).unwrap(), Incoming::Rekey { .. }));
+
+    let close = client.close();
+    assert_eq!(close.content_type, ContentType::Alert);
+    assert_eq!(server.decrypt(&close).unwrap(), Incoming::CloseNotify);
+
+    // Handshake messages never travel inside the channel
+    let (mut client, mut server) = create_channel_pair();
+    let record = Record::handshake(client.encrypt(b"hello").remove(0).payload);
+    assert!(matches!(server.decrypt(&record), Err(ChannelError::InvalidRecord)));
+}
+
+/// Every record of a seeded handshake followed by a re-key in each direction
+fn seeded_rekey_transcript(seed: u64) -> Vec<Vec<u8>> {
+    let config = HandshakeConfig::default();
+    let (client_hello, client_state) =
+        generate_client_hello_with_rng(&config, &mut StdRng::seed_from_u64(seed));
+    let (server_hello, mut server_session) =
+        handle_client_hello_with_rng(client_hello, &config, &mut StdRng::seed_from_u64(seed + 1))
+            .unwrap();
+    let mut client_session = handle_server_hello(server_hello, client_state).unwrap();
+    client_session.verify_finished(&server_session.finished()).unwrap();
+    server_session.verify_finished(&client_session.finished()).unwrap();
+
+    let channel_config = ChannelConfig::default();
+    let client_rng = StdRng::seed_from_u64(seed + 2);
+    let server_rng = StdRng::seed_from_u64(seed + 3);
+    let mut client = SecureChannel::with_rng(client_session, channel_config.clone(), client_rng)
+        .unwrap();
+    let mut server = SecureChannel::with_rng(server_session, channel_config, server_rng).unwrap();
+
+    let mut records = rekey_records(&mut client, &mut server);
+    records.extend(rekey_records(&mut server, &mut client));
+    records
+}
+
+/// One complete re-key started by `initiator`, then a record under the new keys
+fn rekey_records(initiator: &mut SecureChannel, responder: &mut SecureChannel) -> Vec<Vec<u8>> {
+    let request = initiator.rekey().unwrap();
+    let response = reply(responder.decrypt(&request).unwrap()).remove(0);
+    let finished = reply(initiator.decrypt(&response).unwrap()).remove(0);
+    assert!(reply(responder.decrypt(&finished).unwrap()).is_empty());
+    let data = initiator.encrypt(b"after rekey").remove(0);
+    assert_eq!(responder.decrypt(&data).unwrap(), Incoming::Data(b"after rekey".to_vec()));
+    [request, response, finished, data].map(|record| record.to_bytes()).to_vec()
+}
+
+#[test]
+fn test_seeded_rekey_is_reproducible() {
+    let first = seeded_rekey_transcript(42);
+    assert_eq!(first, seeded_rekey_transcript(42));
+    assert_ne!(first, seeded_rekey_transcript(43));
+}
@@ -1,17 +1,49 @@
-use hybrid_kyber_protocol::messages::{ClientHello, ServerHello};
-use hybrid_kyber_protocol::transcript::compute_transcript;
+use crypto::hkdf::HashAlgorithm;
+use hybrid_kyber_protocol::messages::{
+    CipherSuite, ClassicalGroup, ClientHello, DhShare, Extension, KemAlgorithm, KemShare,
+    ServerHello,
+};
+use hybrid_kyber_protocol::transcript::{HandshakeType, Transcript};
+
+const SHA256: HashAlgorithm = HashAlgorithm::Sha256;
+
+fn client_hello() -> ClientHello {
+    ClientHello {
+        versions: vec![2, 1],
+        cipher_suites: vec![
+            CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256,
+            CipherSuite::MLKEM768_X25519_AES256GCM_SHA256,
+        ],
+        kem_shares: vec![KemShare {
+            kem: KemAlgorithm::MLKEM768,
+            public_key: vec![0xAA; 1184],
+        }],
+        dh_shares: vec![DhShare {
+            group: ClassicalGroup::X25519,
+            public_key: vec![0xBB; 32],
+        }],
+        extensions: vec![],
+    }
+}
+
+fn server_hello(cipher_suite: CipherSuite, dh_public_key: [u8; 32]) -> ServerHello {
+    ServerHello {
+        version: 2,
+        cipher_suite,
+        kyber_ct: vec![0xCC; 1088],
+        dh_public_key: dh_public_key.to_vec(),
+        extensions: vec![],
+    }
+}
+
+fn compute_transcript(client_hello: &ClientHello, server_hello: &ServerHello) -> Vec<u8> {
+    Transcript::from_hellos(b"", client_hello, server_hello).snapshot()
+}
 
 #[test]
 fn test_transcript_deterministic() {
-    let ch = ClientHello {
-        version: 1,
-        kyber_pk: vec![0xAA; 1184],
-        x25519_pk: [0xBB; 32],
-    };
-    let sh = ServerHello {
-        kyber_ct: vec![0xCC; 1088],
-        x25519_pk: [0xDD; 32],
-    };
+    let ch = client_hello();
+    let sh = server_hello(CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256, [0xDD; 32]);
 
     let t1 = compute_transcript(&ch, &sh);
     let t2 = compute_transcript(&ch, &sh);
@@ -21,22 +53,127 @@ fn test_transcript_deterministic() {
 
 #[test]
 fn test_transcript_changes_with_input() {
-    let ch = ClientHello {
-        version: 1,
-        kyber_pk: vec![0xAA; 1184],
-        x25519_pk: [0xBB; 32],
-    };
-    let sh1 = ServerHello {
-        kyber_ct: vec![0xCC; 1088],
-        x25519_pk: [0xDD; 32],
-    };
-    let sh2 = ServerHello {
-        kyber_ct: vec![0xCC; 1088],
-        x25519_pk: [0xEE; 32],
-    };
+    let ch = client_hello();
+    let sh1 = server_hello(CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256, [0xDD; 32]);
+    let sh2 = server_hello(CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256, [0xEE; 32]);
 
     let t1 = compute_transcript(&ch, &sh1);
     let t2 = compute_transcript(&ch, &sh2);
 
     assert_ne!(t1, t2);
 }
+
+#[test]
+fn test_transcript_binds_suite_selection() {
+    let ch = client_hello();
+    let sh1 = server_hello(CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256, [0xDD; 32]);
+    let sh2 = server_hello(CipherSuite::MLKEM768_X25519_AES256GCM_SHA256, [0xDD; 32]);
+    assert_ne!(compute_transcript(&ch, &sh1), compute_transcript(&ch, &sh2));
+
+    let mut stripped = client_hello();
+    stripped.cipher_suites.remove(0);
+    assert_ne!(compute_transcript(&ch, &sh2), compute_transcript(&stripped, &sh2));
+}
+
+#[test]
+fn test_transcript_binds_offered_versions() {
+    let sh = server_hello(CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256, [0xDD; 32]);
+    let mut stripped = client_hello();
+    stripped.versions = vec![1];
+
+    assert_ne!(
+        compute_transcript(&client_hello(), &sh),
+        compute_transcript(&stripped, &sh)
+    );
+}
+
+#[test]
+fn test_transcript_covers_extensions() {
+    let ch = client_hello();
+    let sh = server_hello(CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256, [0xDD; 32]);
+    let unknown = Extension {
+        extension_type: 0xFFFF,
+        data: vec![1, 2, 3],
+    };
+
+    let mut ch_ext = client_hello();
+    ch_ext.extensions.push(unknown.clone());
+    assert_ne!(compute_transcript(&ch, &sh), compute_transcript(&ch_ext, &sh));
+
+    let mut sh_ext = sh.clone();
+    sh_ext.extensions.push(unknown);
+    assert_ne!(compute_transcript(&ch, &sh), compute_transcript(&ch, &sh_ext));
+}
+
+#[test]
+fn test_transcript_frames_message_boundaries() {
+    let mut split_early = Transcript::new(SHA256, b"");
+    split_early.absorb(HandshakeType::ClientHello, b"ab");
+    split_early.absorb(HandshakeType::ClientHello, b"c");
+    let mut split_late = Transcript::new(SHA256, b"");
+    split_late.absorb(HandshakeType::ClientHello, b"a");
+    split_late.absorb(HandshakeType::ClientHello, b"bc");
+
+    assert_ne!(split_early.snapshot(), split_late.snapshot());
+}
+
+#[test]
+fn test_transcript_binds_message_types() {
+    let mut as_client_hello = Transcript::new(SHA256, b"");
+    as_client_hello.absorb(HandshakeType::ClientHello, b"message");
+    let mut as_finished = Transcript::new(SHA256, b"");
+    as_finished.absorb(HandshakeType::Finished, b"message");
+
+    assert_ne!(as_client_hello.snapshot(), as_finished.snapshot());
+}
+
+#[test]
+fn test_transcript_is_domain_separated_from_plain_hash() {
+    let ch = client_hello();
+    let sh = server_hello(CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256, [0xDD; 32]);
+
+    assert_ne!(Transcript::new(SHA256, b"").snapshot(), SHA256.digest(b""));
+    assert_ne!(
+        compute_transcript(&ch, &sh),
+        SHA256.digest(&[ch.to_bytes(), sh.to_bytes()].concat())
+    );
+}
+
+#[test]
+fn test_transcript_snapshots_each_stage() {
+    let ch = client_hello();
+    let sh = server_hello(CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256, [0xDD; 32]);
+    let mut transcript = Transcript::from_hellos(b"", &ch, &sh);
+    let hellos = transcript.snapshot();
+    assert_eq!(hellos, transcript.snapshot());
+
+    transcript.absorb(HandshakeType::Finished, &[0x11; 32]);
+    let server_finished = transcript.snapshot();
+    transcript.absorb(HandshakeType::Finished, &[0x22; 32]);
+    let client_finished = transcript.snapshot();
+
+    assert_ne!(hellos, server_finished);
+    assert_ne!(server_finished, client_finished);
+    assert_eq!(hellos, compute_transcript(&ch, &sh));
+}
+
+#[test]
+fn test_transcript_uses_suite_hash() {
+    let ch = client_hello();
+    let sh = server_hello(CipherSuite::MLKEM1024_X448_AES256GCM_SHA384, [0xDD; 32]);
+    let transcript = Transcript::from_hellos(b"", &ch, &sh);
+
+    assert_eq!(transcript.hash(), HashAlgorithm::Sha384);
+    assert_eq!(transcript.snapshot().len(), 48);
+}
+
+#[test]
+fn test_transcript_binds_application_context() {
+    let ch = client_hello();
+    let sh = server_hello(CipherSuite::MLKEM768_X25519_CHACHA20POLY1305_SHA256, [0xDD; 32]);
+    let service_a = Transcript::from_hellos(b"service-a", &ch, &sh).snapshot();
+
+    assert_eq!(service_a, Transcript::from_hellos(b"service-a", &ch, &sh).snapshot());
+    assert_ne!(service_a, Transcript::from_hellos(b"service-b", &ch, &sh).snapshot());
+    assert_ne!(service_a, compute_transcript(&ch, &sh));
+}
@@ -1,9 +1,14 @@
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpListener;
 
-use protocol::framing::{read_frame, write_frame};
-use protocol::handshake::handle_client_hello;
-use protocol::messages::{AppData, ClientHello};
-use protocol::session::SecureChannel;
+use protocol::framing::{read_frame, write_frame, FrameError};
+use protocol::handshake::{
+    handle_client_hello, handle_legacy_client_hello, HandshakeConfig, Session, PROTOCOL_V1,
+};
+use protocol::messages::{Alert, ClientHello, Finished, LegacyAppData, LegacyClientHello};
+use protocol::record::{handshake_message, Record};
+use protocol::session::{Incoming, LegacyChannel, SecureChannel};
+use protocol::Error;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -16,54 +21,180 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         tokio::spawn(async move {
             if let Err(e) = handle_connection(socket).await {
-                eprintln!("Connection error: {:?}", e);
+                eprintln!("Connection error ({:?}, fatal: {}): {}", e.origin(), e.is_fatal(), e);
             }
         });
     }
 }
 
-async fn handle_connection(
-    socket: tokio::net::TcpStream,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let (mut reader, mut writer) = socket.into_split();
+/// Tell the client in the clear why its handshake was rejected; the connection is dropped
+/// either way, so a failed write is ignored
+async fn abort_handshake<W: AsyncWriteExt + Unpin>(writer: &mut W, error: Error) -> Error {
+    if let Some(description) = error.alert() {
+        let _ = write_frame(writer, &Record::alert(Alert::new(description)).to_bytes()).await;
+    }
+    error
+}
 
-    // --- Handshake ---
-    let client_hello_bytes = read_frame(&mut reader).await?;
-    let client_hello =
-        ClientHello::from_bytes(&client_hello_bytes).map_err(|_| "Invalid ClientHello")?;
+/// The body of the client's next handshake message, or the error its alert reports
+async fn read_handshake_message<R: AsyncReadExt + Unpin>(reader: &mut R) -> Result<Vec<u8>, Error> {
+    let record = Record::from_bytes(&read_frame(reader).await?)?;
+    Ok(handshake_message(record)?)
+}
 
-    let (server_hello, session) =
-        handle_client_hello(client_hello).map_err(|e| format!("Handshake failed: {:?}", e))?;
+/// A client's opening message: a ClientHello in a handshake record, or a bare one in the
+/// original v1 wire format
+enum Hello {
+    Current(ClientHello),
+    Legacy(LegacyClientHello),
+}
+
+async fn read_client_hello<R: AsyncReadExt + Unpin>(reader: &mut R) -> Result<Hello, Error> {
+    let frame = read_frame(reader).await?;
+    // A legacy hello starts with its version, which is no record content type
+    if frame.first() == Some(&PROTOCOL_V1) {
+        return Ok(Hello::Legacy(LegacyClientHello::from_bytes(&frame)?));
+    }
+    let record = Record::from_bytes(&frame)?;
+    Ok(Hello::Current(ClientHello::from_bytes(&handshake_message(record)?)?))
+}
 
-    write_frame(&mut writer, &server_hello.to_bytes()).await?;
+/// Run the server's side of the handshake up to a verified client Finished
+async fn handshake<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    client_hello: ClientHello,
+) -> Result<Session, Error>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    let (server_hello, mut session) =
+        handle_client_hello(client_hello, &HandshakeConfig::default())?;
+
+    write_frame(writer, &Record::handshake(server_hello.to_bytes()).to_bytes()).await?;
+    write_frame(writer, &Record::handshake(session.finished().to_bytes()).to_bytes()).await?;
+
+    let client_finished = Finished::from_bytes(&read_handshake_message(reader).await?)?;
+    session.verify_finished(&client_finished)?;
+    Ok(session)
+}
+
+async fn handle_connection(socket: tokio::net::TcpStream) -> Result<(), Error> {
+    let (mut reader, mut writer) = socket.into_split();
+
+    // --- Handshake ---
+    let client_hello = match read_client_hello(&mut reader).await {
+        Ok(Hello::Current(client_hello)) => client_hello,
+        Ok(Hello::Legacy(client_hello)) => {
+            return serve_legacy(&mut reader, &mut writer, client_hello).await;
+        }
+        Err(e) => return Err(abort_handshake(&mut writer, e).await),
+    };
+    let session = match handshake(&mut reader, &mut writer, client_hello).await {
+        Ok(session) => session,
+        Err(e) => return Err(abort_handshake(&mut writer, e).await),
+    };
     println!("Handshake complete!");
 
-    let mut channel = SecureChannel::new(session.keys, session.transcript, false);
+    let mut channel = SecureChannel::new(session)?;
 
     // --- Message Loop ---
     loop {
         let frame = match read_frame(&mut reader).await {
             Ok(f) => f,
-            Err(_) => {
+            // Only a stream that ended after the client's close_notify is a clean disconnect
+            Err(FrameError::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                channel.end_of_stream()?;
                 println!("Client disconnected");
                 break;
             }
+            Err(e) => return Err(e.into()),
+        };
+
+        let record = match Record::from_bytes(&frame) {
+            Ok(record) => record,
+            Err(e) => {
+                write_frame(&mut writer, &channel.alert(e.alert()).to_bytes()).await?;
+                return Err(e.into());
+            }
         };
 
-        let app_data = AppData::from_bytes(&frame).map_err(|_| "Invalid AppData")?;
+        let incoming = match channel.decrypt(&record) {
+            Ok(incoming) => incoming,
+            Err(e) => {
+                if let Some(description) = e.alert() {
+                    write_frame(&mut writer, &channel.alert(description).to_bytes()).await?;
+                }
+                // A replayed record is dropped; anything else ends the connection
+                let e = Error::from(e);
+                if e.is_fatal() {
+                    return Err(e);
+                }
+                continue;
+            }
+        };
 
-        let plaintext = channel
-            .decrypt(&app_data)
-            .map_err(|e| format!("Decryption failed: {:?}", e))?;
+        let plaintext = match incoming {
+            Incoming::Data(plaintext) => plaintext,
+            Incoming::KeyUpdate => continue,
+            Incoming::Rekey { reply } => {
+                for record in reply {
+                    write_frame(&mut writer, &record.to_bytes()).await?;
+                }
+                continue;
+            }
+            Incoming::CloseNotify => {
+                write_frame(&mut writer, &channel.close().to_bytes()).await?;
+                continue;
+            }
+        };
 
         let message = String::from_utf8_lossy(&plaintext);
         println!("[recv] {}", message);
 
         // Echo back with prefix
         let response = format!("Server received: {}", message);
-        let encrypted = channel.encrypt(response.as_bytes());
-        write_frame(&mut writer, &encrypted.to_bytes()).await?;
+        for record in channel.encrypt(response.as_bytes()) {
+            write_frame(&mut writer, &record.to_bytes()).await?;
+        }
     }
 
     Ok(())
 }
+
+/// Serve a client of the original v1 wire format: bare hellos and `LegacyAppData` frames,
+/// with no Finished, alerts or close_notify
+async fn serve_legacy<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    client_hello: LegacyClientHello,
+) -> Result<(), Error>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    let (server_hello, session) =
+        handle_legacy_client_hello(client_hello, &HandshakeConfig::default())?;
+    write_frame(writer, &server_hello.to_bytes()).await?;
+    println!("Handshake complete! (legacy v1 client)");
+
+    let mut channel = LegacyChannel::new(session);
+    loop {
+        let frame = match read_frame(reader).await {
+            Ok(f) => f,
+            Err(FrameError::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                println!("Client disconnected");
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let plaintext = channel.decrypt(&LegacyAppData::from_bytes(&frame)?)?;
+        let message = String::from_utf8_lossy(&plaintext);
+        println!("[recv] {}", message);
+
+        let response = format!("Server received: {}", message);
+        write_frame(writer, &channel.encrypt(response.as_bytes()).to_bytes()).await?;
+    }
+}